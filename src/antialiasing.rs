@@ -0,0 +1,82 @@
+use nalgebra_glm::Vec2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntiAliasing {
+    Off,
+    Ssaa(usize), // Se dibuja con `n` muestras por pixel a mayor resolución y se reduce
+    Msaa(usize), // Cobertura y profundidad por muestra, pero un solo sombreado por pixel
+    Fxaa,        // Filtro de postproceso sobre el cuadro ya mapeado; el más barato
+}
+
+// Orden en que se recorren los modos con la tecla X
+const MODES: [AntiAliasing; 8] = [
+    AntiAliasing::Off,
+    AntiAliasing::Fxaa,
+    AntiAliasing::Msaa(2),
+    AntiAliasing::Msaa(4),
+    AntiAliasing::Msaa(8),
+    AntiAliasing::Ssaa(2),
+    AntiAliasing::Ssaa(4),
+    AntiAliasing::Ssaa(8),
+];
+
+impl AntiAliasing {
+    // Acepta `off`, `fxaa`, `msaa2`, `msaa4`, `msaa8`, `ssaa2`, `ssaa4` y `ssaa8`
+    pub fn parse(name: &str) -> Option<Self> {
+        MODES.iter().copied().find(|mode| mode.name() == name.trim().to_lowercase())
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AntiAliasing::Off => "off",
+            AntiAliasing::Fxaa => "fxaa",
+            AntiAliasing::Msaa(2) => "msaa2",
+            AntiAliasing::Msaa(4) => "msaa4",
+            AntiAliasing::Msaa(_) => "msaa8",
+            AntiAliasing::Ssaa(2) => "ssaa2",
+            AntiAliasing::Ssaa(4) => "ssaa4",
+            AntiAliasing::Ssaa(_) => "ssaa8",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = MODES.iter().position(|mode| mode == self).unwrap_or(0);
+        MODES[(index + 1) % MODES.len()]
+    }
+
+    // Factor por eje del buffer de supersampling: 2 muestras = √2 por eje, 4 muestras = 2 por eje
+    pub fn render_scale(&self) -> f32 {
+        match self {
+            AntiAliasing::Ssaa(samples) => (*samples as f32).sqrt(),
+            _ => 1.0,
+        }
+    }
+
+    // Posiciones de muestreo dentro del pixel (0..1) que usa el rasterizador
+    pub fn sample_pattern(&self) -> Vec<Vec2> {
+        match self {
+            AntiAliasing::Msaa(samples) => sample_pattern(*samples),
+            _ => sample_pattern(1),
+        }
+    }
+}
+
+// Patrones estándar de D3D: rejillas rotadas que cubren bien bordes casi horizontales y verticales
+pub fn sample_pattern(samples: usize) -> Vec<Vec2> {
+    let offsets: &[(f32, f32)] = match samples {
+        2 => &[(0.75, 0.75), (0.25, 0.25)],
+        4 => &[(0.375, 0.125), (0.875, 0.375), (0.125, 0.625), (0.625, 0.875)],
+        8 => &[
+            (0.5625, 0.3125),
+            (0.4375, 0.6875),
+            (0.8125, 0.5625),
+            (0.3125, 0.1875),
+            (0.1875, 0.8125),
+            (0.0625, 0.4375),
+            (0.6875, 0.9375),
+            (0.9375, 0.0625),
+        ],
+        _ => &[(0.5, 0.5)],
+    };
+    offsets.iter().map(|&(x, y)| Vec2::new(x, y)).collect()
+}
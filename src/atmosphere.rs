@@ -0,0 +1,119 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+const VIEW_SAMPLES: usize = 8;   // Muestras a lo largo del rayo de la cámara
+const LIGHT_SAMPLES: usize = 4;  // Muestras a lo largo del rayo hacia el sol
+const SUN_INTENSITY: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Atmosphere {
+    pub color: Vec3,  // Coeficientes Rayleigh relativos por canal (el tono del cielo)
+    pub density: f32, // Escala todos los coeficientes de dispersión
+    pub height: f32,  // Grosor de la capa relativo al radio del planeta
+    pub mie: f32,     // Coeficiente Mie (bruma y polvo, casi gris)
+    pub mie_g: f32,   // Anisotropía de Mie: qué tanto se concentra la luz hacia adelante
+}
+
+impl Atmosphere {
+    pub fn new(color: Vec3, density: f32, height: f32, mie: f32, mie_g: f32) -> Self {
+        Atmosphere { color, density, height, mie, mie_g }
+    }
+
+    // Luz dispersada hacia la cámara a lo largo de un rayo que cruza la atmósfera, junto con la
+    // fracción de lo que hay detrás que logra atravesarla.
+    // Las posiciones están en espacio de mundo; internamente se trabaja en radios del planeta.
+    pub fn scatter(&self, eye: Vec3, dir: Vec3, planet_center: Vec3, planet_radius: f32, sun_position: Vec3) -> (Vec3, Vec3) {
+        let origin = (eye - planet_center) / planet_radius;
+        let sun = (sun_position - planet_center) / planet_radius;
+        let top = 1.0 + self.height;
+
+        let (t_near, t_far) = match ray_sphere(&origin, &dir, top) {
+            Some(hits) => hits,
+            None => return (Vec3::zeros(), Vec3::repeat(1.0)),
+        };
+        let t_near = t_near.max(0.0);
+        // El rayo termina donde choca con la superficie
+        let t_far = match ray_sphere(&origin, &dir, 1.0) {
+            Some((hit, _)) if hit > 0.0 => t_far.min(hit),
+            _ => t_far,
+        };
+        if t_far <= t_near {
+            return (Vec3::zeros(), Vec3::repeat(1.0));
+        }
+
+        let rayleigh_height = self.height * 0.25;
+        let mie_height = self.height * 0.1;
+        let beta_rayleigh = self.color * self.density;
+        let beta_mie = self.mie * self.density;
+
+        let step = (t_far - t_near) / VIEW_SAMPLES as f32;
+        let mut view_depth_r = 0.0;
+        let mut view_depth_m = 0.0;
+        let mut sum_r = Vec3::zeros();
+        let mut sum_m = Vec3::zeros();
+
+        for i in 0..VIEW_SAMPLES {
+            let p = origin + dir * (t_near + step * (i as f32 + 0.5));
+            let h = (p.magnitude() - 1.0).max(0.0);
+            let density_r = (-h / rayleigh_height).exp() * step;
+            let density_m = (-h / mie_height).exp() * step;
+            view_depth_r += density_r;
+            view_depth_m += density_m;
+
+            // Los puntos en la sombra del planeta no reciben luz directa
+            let light_dir = (sun - p).normalize();
+            if let Some((hit, _)) = ray_sphere(&p, &light_dir, 1.0) {
+                if hit > 0.0 {
+                    continue;
+                }
+            }
+
+            let (_, t_light) = ray_sphere(&p, &light_dir, top).unwrap_or((0.0, 0.0));
+            let light_step = t_light / LIGHT_SAMPLES as f32;
+            let mut light_depth_r = 0.0;
+            let mut light_depth_m = 0.0;
+            for j in 0..LIGHT_SAMPLES {
+                let q = p + light_dir * (light_step * (j as f32 + 0.5));
+                let hq = (q.magnitude() - 1.0).max(0.0);
+                light_depth_r += (-hq / rayleigh_height).exp() * light_step;
+                light_depth_m += (-hq / mie_height).exp() * light_step;
+            }
+
+            // La extinción roba más azul que rojo: de ahí los terminadores rojizos
+            let tau = beta_rayleigh * (view_depth_r + light_depth_r)
+                + Vec3::repeat(beta_mie * 1.1 * (view_depth_m + light_depth_m));
+            let attenuation = tau.map(|t| (-t).exp());
+
+            let mu = dir.dot(&light_dir);
+            sum_r += attenuation * density_r * rayleigh_phase(mu);
+            sum_m += attenuation * density_m * mie_phase(mu, self.mie_g);
+        }
+
+        let inscatter = (beta_rayleigh.component_mul(&sum_r) + sum_m * beta_mie) * SUN_INTENSITY;
+        let view_tau = beta_rayleigh * view_depth_r + Vec3::repeat(beta_mie * 1.1 * view_depth_m);
+        (inscatter, view_tau.map(|t| (-t).exp()))
+    }
+}
+
+fn rayleigh_phase(mu: f32) -> f32 {
+    3.0 / (16.0 * PI) * (1.0 + mu * mu)
+}
+
+// Henyey-Greenstein con la corrección de Cornette-Shanks
+fn mie_phase(mu: f32, g: f32) -> f32 {
+    let g2 = g * g;
+    3.0 / (8.0 * PI) * ((1.0 - g2) * (1.0 + mu * mu))
+        / ((2.0 + g2) * (1.0 + g2 - 2.0 * g * mu).powf(1.5))
+}
+
+// Distancias de entrada y salida de un rayo en una esfera centrada en el origen
+fn ray_sphere(origin: &Vec3, dir: &Vec3, radius: f32) -> Option<(f32, f32)> {
+    let b = origin.dot(dir);
+    let c = origin.dot(origin) - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    Some((-b - root, -b + root))
+}
@@ -0,0 +1,167 @@
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Pesos de un Gaussiano de 9 muestras (centro y un lado)
+const GAUSSIAN_WEIGHTS: [f32; 5] = [0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216];
+
+pub struct Bloom {
+    pub enabled: bool,
+    pub threshold: f32, // Luminancia a partir de la cual la luz se derrama
+    pub knee: f32,      // Suaviza la transición alrededor del umbral
+    pub intensity: f32, // Cuánto del resplandor se suma de vuelta
+    pub levels: usize,  // Niveles de la cadena de mips; más niveles = halo más amplio
+}
+
+// Imagen HDR auxiliar para los niveles de la cadena
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Bloom {
+    pub fn new(threshold: f32, knee: f32, intensity: f32, levels: usize) -> Self {
+        Bloom {
+            enabled: true,
+            threshold,
+            knee,
+            intensity,
+            levels,
+        }
+    }
+
+    // Extrae las zonas brillantes, las desenfoca en varios niveles y las suma sobre el cuadro HDR
+    pub fn apply(&self, framebuffer: &mut Framebuffer) {
+        if !self.enabled || self.levels == 0 {
+            return;
+        }
+
+        let source = Image {
+            width: framebuffer.width,
+            height: framebuffer.height,
+            pixels: framebuffer.color.clone(),
+        };
+
+        // Bright-pass a media resolución y luego una cadena de reducciones desenfocadas
+        let mut chain = vec![self.bright_pass(&downsample(&source))];
+        while chain.len() < self.levels {
+            let last = chain.last().unwrap();
+            if last.width < 4 || last.height < 4 {
+                break;
+            }
+            chain.push(downsample(last));
+        }
+        for level in chain.iter_mut() {
+            blur(level);
+        }
+
+        // Recombinamos de lo más pequeño a lo más grande
+        for i in (1..chain.len()).rev() {
+            let (upper, lower) = chain.split_at_mut(i);
+            add_upsampled(&mut upper[i - 1], &lower[0], 1.0);
+        }
+
+        let mut target = Image {
+            width: framebuffer.width,
+            height: framebuffer.height,
+            pixels: std::mem::take(&mut framebuffer.color),
+        };
+        add_upsampled(&mut target, &chain[0], self.intensity / chain.len() as f32);
+        framebuffer.color = target.pixels;
+    }
+
+    // Umbral con rodilla suave para que el halo no aparezca de golpe
+    fn bright_pass(&self, image: &Image) -> Image {
+        let knee = self.knee.max(1e-4);
+        let pixels = image
+            .pixels
+            .iter()
+            .map(|color| {
+                let brightness = color.r.max(color.g).max(color.b);
+                let soft = (brightness - self.threshold + knee).clamp(0.0, 2.0 * knee);
+                let soft = soft * soft / (4.0 * knee);
+                let weight = soft.max(brightness - self.threshold) / brightness.max(1e-4);
+                *color * weight
+            })
+            .collect();
+        Image { width: image.width, height: image.height, pixels }
+    }
+}
+
+// Promedio de bloques de 2x2
+fn downsample(image: &Image) -> Image {
+    let width = (image.width / 2).max(1);
+    let height = (image.height / 2).max(1);
+    let mut pixels = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        for x in 0..width {
+            let sx = (x * 2).min(image.width - 1);
+            let sy = (y * 2).min(image.height - 1);
+            let sx1 = (sx + 1).min(image.width - 1);
+            let sy1 = (sy + 1).min(image.height - 1);
+            let sum = image.pixels[sy * image.width + sx]
+                + image.pixels[sy * image.width + sx1]
+                + image.pixels[sy1 * image.width + sx]
+                + image.pixels[sy1 * image.width + sx1];
+            pixels.push(sum * 0.25);
+        }
+    }
+
+    Image { width, height, pixels }
+}
+
+// Gaussiano separable: una pasada horizontal y una vertical
+fn blur(image: &mut Image) {
+    let (width, height) = (image.width, image.height);
+    let mut temp = vec![Color::black(); width * height];
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = image.pixels[y * width + x] * GAUSSIAN_WEIGHTS[0];
+            for (offset, weight) in GAUSSIAN_WEIGHTS.iter().enumerate().skip(1) {
+                let left = x.saturating_sub(offset);
+                let right = (x + offset).min(width - 1);
+                sum = sum + (image.pixels[y * width + left] + image.pixels[y * width + right]) * *weight;
+            }
+            temp[y * width + x] = sum;
+        }
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = temp[y * width + x] * GAUSSIAN_WEIGHTS[0];
+            for (offset, weight) in GAUSSIAN_WEIGHTS.iter().enumerate().skip(1) {
+                let up = y.saturating_sub(offset);
+                let down = (y + offset).min(height - 1);
+                sum = sum + (temp[up * width + x] + temp[down * width + x]) * *weight;
+            }
+            image.pixels[y * width + x] = sum;
+        }
+    }
+}
+
+// Suma `source` sobre `target` con interpolación bilineal
+fn add_upsampled(target: &mut Image, source: &Image, strength: f32) {
+    let scale_x = source.width as f32 / target.width as f32;
+    let scale_y = source.height as f32 / target.height as f32;
+
+    for y in 0..target.height {
+        let fy = ((y as f32 + 0.5) * scale_y - 0.5).max(0.0);
+        let y0 = (fy as usize).min(source.height - 1);
+        let y1 = (y0 + 1).min(source.height - 1);
+        let ty = fy - y0 as f32;
+
+        for x in 0..target.width {
+            let fx = ((x as f32 + 0.5) * scale_x - 0.5).max(0.0);
+            let x0 = (fx as usize).min(source.width - 1);
+            let x1 = (x0 + 1).min(source.width - 1);
+            let tx = fx - x0 as f32;
+
+            let top = source.pixels[y0 * source.width + x0].lerp(&source.pixels[y0 * source.width + x1], tx);
+            let bottom = source.pixels[y1 * source.width + x0].lerp(&source.pixels[y1 * source.width + x1], tx);
+            let index = y * target.width + x;
+            target.pixels[index] = target.pixels[index] + top.lerp(&bottom, ty) * strength;
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt;
+
+// Fechas del calendario y días julianos. La simulación corre en TDB, la escala de tiempo de los
+// elementos orbitales; difiere de la hora civil (UTC) en poco más de un minuto en la actualidad.
+
+// 15 de octubre de 1582, el primer día del calendario gregoriano; las fechas anteriores son julianas
+const GREGORIAN_REFORM: (i32, u32, u32) = (1582, 10, 15);
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+// Fecha con precisión de un segundo. El año es astronómico: el 1 a. C. es el año 0 y el 2 a. C., el -1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalendarDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl CalendarDate {
+    // Meeus, Astronomical Algorithms, cap. 7. Como en el libro, vale para días julianos no negativos.
+    pub fn julian_day(&self) -> f64 {
+        let (mut year, mut month) = (self.year as f64, self.month as f64);
+        if month <= 2.0 {
+            year -= 1.0;
+            month += 12.0;
+        }
+        let seconds = self.hour as f64 * 3600.0 + self.minute as f64 * 60.0 + self.second as f64;
+        let day = self.day as f64 + seconds / SECONDS_PER_DAY;
+        let julian = (365.25 * (year + 4716.0)).floor() + (30.6001 * (month + 1.0)).floor() + day - 1524.5;
+        if (self.year, self.month, self.day) < GREGORIAN_REFORM {
+            return julian;
+        }
+        let century = (year / 100.0).floor();
+        julian + 2.0 - century + (century / 4.0).floor()
+    }
+
+    // Se redondea al segundo antes de separar la fecha, para no mostrar nunca 60 segundos
+    pub fn from_julian_day(julian_day: f64) -> Self {
+        let seconds = ((julian_day + 0.5) * SECONDS_PER_DAY).round();
+        let z = (seconds / SECONDS_PER_DAY).floor();
+        let second_of_day = (seconds - z * SECONDS_PER_DAY) as u32;
+
+        // 2299161 es el día juliano entero del 15 de octubre de 1582
+        let a = if z < 2299161.0 {
+            z
+        } else {
+            let alpha = ((z - 1867216.25) / 36524.25).floor();
+            z + 1.0 + alpha - (alpha / 4.0).floor()
+        };
+        let b = a + 1524.0;
+        let c = ((b - 122.1) / 365.25).floor();
+        let d = (365.25 * c).floor();
+        let e = ((b - d) / 30.6001).floor();
+        let month = if e < 14.0 { e - 1.0 } else { e - 13.0 };
+        let year = if month > 2.0 { c - 4716.0 } else { c - 4715.0 };
+
+        CalendarDate {
+            year: year as i32,
+            month: month as u32,
+            day: (b - d - (30.6001 * e).floor()) as u32,
+            hour: second_of_day / 3600,
+            minute: second_of_day / 60 % 60,
+            second: second_of_day % 60,
+        }
+    }
+
+    // `AAAA-MM-DD`, opcionalmente seguida de `HH:MM` o `HH:MM:SS` tras un espacio o una T
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (date, time) = text.split_once([' ', 'T']).unwrap_or((text, ""));
+        let (sign, date) = match date.strip_prefix('-') {
+            Some(date) => (-1, date),
+            None => (1, date),
+        };
+        let mut fields = date.split('-').map(|field| field.parse::<u32>().ok());
+        let year = fields.next()?? as i32 * sign;
+        let month = fields.next()??;
+        let day = fields.next()??;
+        if fields.next().is_some() {
+            return None;
+        }
+
+        let mut clock = [0; 3];
+        let time = time.trim();
+        if !time.is_empty() {
+            let fields: Vec<&str> = time.split(':').collect();
+            if !(2..=3).contains(&fields.len()) {
+                return None;
+            }
+            for (value, field) in clock.iter_mut().zip(fields) {
+                *value = field.parse().ok()?;
+            }
+        }
+
+        let date = CalendarDate { year, month, day, hour: clock[0], minute: clock[1], second: clock[2] };
+        // La vuelta por el día juliano descarta el 30 de febrero, los días que se saltó la reforma
+        // gregoriana, las horas imposibles y las fechas anteriores al día juliano cero
+        let julian_day = date.julian_day();
+        (julian_day >= 0.0 && CalendarDate::from_julian_day(julian_day) == date).then_some(date)
+    }
+}
+
+impl fmt::Display for CalendarDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.year < 0 {
+            write!(f, "-{:04}", -self.year)?;
+        } else {
+            write!(f, "{:04}", self.year)?;
+        }
+        write!(f, "-{:02}-{:02} {:02}:{:02}:{:02}", self.month, self.day, self.hour, self.minute, self.second)
+    }
+}
+
+// Día juliano de un instante escrito como fecha del calendario o como día juliano: `JD 2451545.0`. El
+// prefijo es obligatorio; un número suelto como `2000` parece un año y no se toma como día juliano.
+pub fn parse_instant(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let julian_day = match text.strip_prefix("JD").or_else(|| text.strip_prefix("jd")) {
+        Some(number) => number.trim().parse::<f64>().ok()?,
+        None => CalendarDate::parse(text)?.julian_day(),
+    };
+    (julian_day.is_finite() && julian_day >= 0.0).then_some(julian_day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn date(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> CalendarDate {
+        CalendarDate { year, month, day, hour, minute, second: 0 }
+    }
+
+    // Meeus, tabla 7.a y ejemplos 7.a y 7.b
+    const MEEUS: [(CalendarDate, f64); 18] = [
+        (date(2000, 1, 1, 12, 0), 2451545.0),
+        (date(1999, 1, 1, 0, 0), 2451179.5),
+        (date(1987, 1, 27, 0, 0), 2446822.5),
+        (date(1987, 6, 19, 12, 0), 2446966.0),
+        (date(1988, 1, 27, 0, 0), 2447187.5),
+        (date(1988, 6, 19, 12, 0), 2447332.0),
+        (date(1900, 1, 1, 0, 0), 2415020.5),
+        (date(1600, 1, 1, 0, 0), 2305447.5),
+        (date(1600, 12, 31, 0, 0), 2305812.5),
+        (date(837, 4, 10, 7, 12), 2026871.8),
+        (date(-123, 12, 31, 0, 0), 1676496.5),
+        (date(-122, 1, 1, 0, 0), 1676497.5),
+        (date(-1000, 7, 12, 12, 0), 1356001.0),
+        (date(-1000, 2, 29, 0, 0), 1355866.5),
+        (date(-1001, 8, 17, 21, 36), 1355671.4),
+        (date(-4712, 1, 1, 12, 0), 0.0),
+        (date(333, 1, 27, 12, 0), 1842713.0),
+        (date(1582, 10, 4, 0, 0), 2299159.5),
+    ];
+
+    #[test]
+    fn julian_day_matches_meeus() {
+        for (date, expected) in MEEUS {
+            let julian_day = date.julian_day();
+            assert!((julian_day - expected).abs() < 1e-6, "{date}: {julian_day} en vez de {expected}");
+        }
+    }
+
+    #[test]
+    fn julian_day_round_trips() {
+        for (date, expected) in MEEUS {
+            assert_eq!(CalendarDate::from_julian_day(expected), date);
+        }
+        // El día siguiente al 4 de octubre de 1582 es el 15
+        assert_eq!(CalendarDate::from_julian_day(2299160.5), date(1582, 10, 15, 0, 0));
+        // Ejemplo 7.a: el lanzamiento del Sputnik, 1957-10-04 19:26:24
+        let sputnik = CalendarDate { year: 1957, month: 10, day: 4, hour: 19, minute: 26, second: 24 };
+        assert!((sputnik.julian_day() - 2436116.31).abs() < 1e-6);
+        assert_eq!(CalendarDate::from_julian_day(sputnik.julian_day()), sputnik);
+    }
+
+    #[test]
+    fn parses_dates_and_julian_days() {
+        assert_eq!(CalendarDate::parse("2000-01-01 12:00"), Some(date(2000, 1, 1, 12, 0)));
+        assert_eq!(CalendarDate::parse("1957-10-04T19:26:24").map(|date| date.second), Some(24));
+        assert_eq!(CalendarDate::parse("-1000-02-29"), Some(date(-1000, 2, 29, 0, 0)));
+        assert_eq!(CalendarDate::parse("2000-02-29"), Some(date(2000, 2, 29, 0, 0)));
+        assert_eq!(parse_instant("JD 2451545"), Some(2451545.0));
+        assert_eq!(parse_instant("jd2451545.0"), Some(2451545.0));
+        assert_eq!(parse_instant(" 2000-01-01 12:00 "), Some(2451545.0));
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for text in [
+            "1582-10-10",       // Uno de los días que se saltó la reforma gregoriana
+            "2001-02-29",       // 2001 no es bisiesto
+            "1900-02-29",       // Los siglos no divisibles por 400 tampoco
+            "2000-13-01",
+            "2000-04-31",
+            "2000-01-01 24:00",
+            "2000-01-01 12:60",
+            "2000-01-01 12",
+            "-4713-12-31",      // Anterior al día juliano cero
+            "2000-01",
+            "2000-01-01-01",
+            "abc",
+            "",
+        ] {
+            assert_eq!(parse_instant(text), None, "`{text}` no debería aceptarse");
+        }
+        assert_eq!(parse_instant("JD -1"), None);
+    }
+
+    #[test]
+    fn bare_numbers_are_not_julian_days() {
+        for text in ["2000", "2451545.0", "-1", "JD", "JD 2000-01-01"] {
+            assert_eq!(parse_instant(text), None, "`{text}` no debería aceptarse");
+        }
+    }
+}
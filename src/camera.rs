@@ -1,34 +1,92 @@
-use nalgebra_glm::{Vec3, rotate_vec3};
+use nalgebra_glm::{DVec3, Vec3, Vec4, Mat4, rotate_vec3, look_at};
+use crate::depth::{DepthMode, DepthRange};
+use crate::picking::Ray;
+use crate::settings::Settings;
 use std::f32::consts::PI;
 
+// Campo de visión vertical por defecto, en radianes
+pub const DEFAULT_FOV: f32 = 45.0 * PI / 180.0;
+// Límites del zoom con la rueda del ratón
+pub const MIN_FOV: f32 = 1.0 * PI / 180.0;
+pub const MAX_FOV: f32 = 120.0 * PI / 180.0;
+
+// El ojo y el centro están en coordenadas del mundo en f64, como las posiciones de los cuerpos. Para
+// dibujar, todo se lleva primero al espacio relativo al ojo con `relative`.
 pub struct Camera {
-  pub eye: Vec3,
-  pub center: Vec3,
+  pub eye: DVec3,
+  pub center: DVec3,
   pub up: Vec3,
-  pub has_changed: bool
+  pub fov: f32, // Vertical, en radianes
+  pub near: f32,
+  pub far: f32,
+  pub depth: DepthMode,
 }
 
 impl Camera {
-  pub fn new(eye: Vec3, center: Vec3, up: Vec3) -> Self {
+  pub fn new(eye: DVec3, center: DVec3, up: Vec3) -> Self {
     Camera {
       eye,
       center,
       up,
-      has_changed: true,
+      fov: DEFAULT_FOV,
+      near: 0.1,
+      far: 1000.0,
+      depth: DepthMode::Standard,
+    }
+  }
+
+  // Parámetros de la proyección de la sección `[camera]`: `fov` en grados, `near`, `far` y `depth`
+  pub fn set_projection(&mut self, settings: &Settings) {
+    self.fov = settings.get_f32("camera.fov", DEFAULT_FOV.to_degrees()).to_radians().clamp(MIN_FOV, MAX_FOV);
+    self.near = settings.get_f32("camera.near", 0.1).max(1e-6);
+    self.far = settings.get_f32("camera.far", 1000.0).max(self.near * 2.0);
+    if let Some(name) = settings.get("camera.depth") {
+      match DepthMode::parse(name) {
+        Some(depth) => self.depth = depth,
+        None => eprintln!("Modo de profundidad desconocido: {}", name),
+      }
     }
   }
 
-  pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
-    let forward = (self.center - self.eye).normalize();
-    let right = forward.cross(&self.up).normalize();
-    let up = right.cross(&forward).normalize();
+  pub fn depth_range(&self) -> DepthRange {
+    DepthRange { near: self.near, far: self.far, mode: self.depth }
+  }
+
+  // Zoom óptico: cada paso angosta (o abre, si es negativo) el campo de visión un 10 %
+  pub fn zoom_fov(&mut self, steps: f32) {
+    self.fov = (self.fov * 0.9f32.powf(steps)).clamp(MIN_FOV, MAX_FOV);
+  }
 
-    let rotated = 
-    vector.x * right +
-    vector.y * up +
-    - vector.z * forward;
+  // Posición relativa al ojo. La resta se hace en f64 y recién después se pasa a f32, así lo que está
+  // cerca de la cámara conserva toda la precisión aunque quede lejísimos del origen del mundo.
+  pub fn relative(&self, point: &DVec3) -> Vec3 {
+    (point - self.eye).cast()
+  }
 
-    rotated.normalize()
+  // Dirección de la mirada, normalizada
+  pub fn forward(&self) -> Vec3 {
+    self.relative(&self.center).normalize()
+  }
+
+  // Vista con el ojo en el origen: los modelos se trasladan con `relative`
+  pub fn view_matrix(&self) -> Mat4 {
+    look_at(&Vec3::zeros(), &self.relative(&self.center), &self.up)
+  }
+
+  // Rayo que sale del ojo y pasa por el punto (x, y) de la pantalla, en pixeles desde la esquina
+  // superior izquierda, en el espacio relativo al ojo. Se desproyectan el plano cercano y el z = 0 de NDC con la inversa de vista-proyección;
+  // el plano lejano no sirve porque con `far` enorme queda en el infinito.
+  pub fn screen_ray(&self, x: f32, y: f32, width: f32, height: f32, projection_matrix: &Mat4) -> Ray {
+    let inverse = (projection_matrix * self.view_matrix()).try_inverse().unwrap_or(Mat4::identity());
+    let ndc_x = x / width * 2.0 - 1.0;
+    let ndc_y = 1.0 - y / height * 2.0;
+    let unproject = |z: f32| {
+      let point = inverse * Vec4::new(ndc_x, ndc_y, z, 1.0);
+      point.xyz() / point.w
+    };
+    let near = unproject(-1.0);
+    let middle = unproject(0.0);
+    Ray::new(Vec3::zeros(), middle - near)
   }
 
   pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
@@ -40,47 +98,36 @@ impl Camera {
     let radius_xz = (radius_vector.x * radius_vector.x + radius_vector.z * radius_vector.z).sqrt();
     let current_pitch = (-radius_vector.y).atan2(radius_xz);
 
-    let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
-    let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+    let new_yaw = (current_yaw + delta_yaw as f64) % (2.0 * PI as f64);
+    let max_pitch = (PI / 2.0 - 0.1) as f64;
+    let new_pitch = (current_pitch + delta_pitch as f64).clamp(-max_pitch, max_pitch);
 
-    let new_eye = self.center + Vec3::new(
+    let new_eye = self.center + DVec3::new(
       radius * new_yaw.cos() * new_pitch.cos(),
       -radius * new_pitch.sin(),
       radius * new_yaw.sin() * new_pitch.cos()
     );
 
     self.eye = new_eye;
-    self.has_changed = true;
   }
 
   pub fn zoom(&mut self, delta: f32) {
     let direction = (self.center - self.eye).normalize();
-    self.eye += direction * delta;
-    self.has_changed = true;
+    self.eye += direction * delta as f64;
   }
 
   pub fn move_center(&mut self, direction: Vec3) {
     let radius_vector = self.center - self.eye;
     let radius = radius_vector.magnitude();
 
-    let angle_x = direction.x * 0.05; // Adjust this factor to control rotation speed
-    let angle_y = direction.y * 0.05;
+    let angle_x = direction.x as f64 * 0.05; // Adjust this factor to control rotation speed
+    let angle_y = direction.y as f64 * 0.05;
 
-    let rotated = rotate_vec3(&radius_vector, angle_x, &Vec3::new(0.0, 1.0, 0.0));
+    let rotated = rotate_vec3(&radius_vector, angle_x, &DVec3::new(0.0, 1.0, 0.0));
 
-    let right = rotated.cross(&self.up).normalize();
+    let right = rotated.cross(&self.up.cast()).normalize();
     let final_rotated = rotate_vec3(&rotated, angle_y, &right);
 
     self.center = self.eye + final_rotated.normalize() * radius;
-    self.has_changed = true;
-  }
-
-  pub fn check_if_changed(&mut self) -> bool {
-    if self.has_changed {
-      self.has_changed = false;
-      true
-    } else {
-      false
-    }
   }
 }
\ No newline at end of file
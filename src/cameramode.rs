@@ -0,0 +1,86 @@
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::camerapath::Playback;
+use crate::cuerpo::Cuerpo;
+use crate::follow::Follow;
+use crate::freefly::{Flight, FreeFly};
+use crate::viewpoint::Viewpoint;
+
+// Quién mueve la cámara. Todos los cambios parten de la vista actual, así que la imagen no salta:
+// el vuelo libre arranca con la orientación de la cámara, el seguimiento llega al cuerpo con una
+// transición y al volver a la órbita se endereza el horizonte de la misma forma.
+pub enum CameraMode {
+    Orbit,            // Controles de órbita alrededor del centro
+    Follow(Follow),   // El centro sigue a un cuerpo y se sigue orbitando a su alrededor
+    FreeFly(FreeFly), // Seis grados de libertad
+    Path(Playback),   // Un recorrido de cámara grabado
+}
+
+impl CameraMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraMode::Orbit => "órbita",
+            CameraMode::Follow(_) => "seguimiento",
+            CameraMode::FreeFly(_) => "vuelo libre",
+            CameraMode::Path(_) => "recorrido",
+        }
+    }
+
+    // Línea del HUD
+    pub fn status(&self, bodies: &[Cuerpo]) -> String {
+        match self {
+            CameraMode::Orbit => "Cámara en órbita".to_string(),
+            CameraMode::Follow(follow) => format!("Siguiendo {} (marco {})", bodies[follow.target].name, follow.frame.name()),
+            CameraMode::FreeFly(free_fly) => format!("Vuelo libre, velocidad {:.2}", free_fly.speed()),
+            CameraMode::Path(playback) => format!("Recorrido {:.1} de {:.1} s", playback.time().min(playback.duration), playback.duration),
+        }
+    }
+
+    pub fn follow(&self) -> Option<&Follow> {
+        match self {
+            CameraMode::Follow(follow) => Some(follow),
+            _ => None,
+        }
+    }
+
+    pub fn follow_mut(&mut self) -> Option<&mut Follow> {
+        match self {
+            CameraMode::Follow(follow) => Some(follow),
+            _ => None,
+        }
+    }
+
+    pub fn is_free_fly(&self) -> bool {
+        matches!(self, CameraMode::FreeFly(_))
+    }
+
+    pub fn is_path(&self) -> bool {
+        matches!(self, CameraMode::Path(_))
+    }
+
+    // Si las flechas y WASDQE orbitan la cámara
+    pub fn uses_orbit_controls(&self) -> bool {
+        matches!(self, CameraMode::Orbit | CameraMode::Follow(_))
+    }
+
+    // Vuelve a la órbita alrededor del centro actual. Si venía del vuelo libre o de un recorrido, devuelve
+    // la vista con el horizonte derecho a la que hay que llevar la cámara, porque la órbita gira alrededor
+    // del eje y.
+    pub fn enter_orbit(&mut self, camera: &Camera) -> Option<Viewpoint> {
+        let may_be_tilted = !self.uses_orbit_controls();
+        *self = CameraMode::Orbit;
+        (may_be_tilted && camera.up != Vec3::y()).then(|| Viewpoint { up: Vec3::y(), ..Viewpoint::from_camera(camera) })
+    }
+
+    pub fn enter_free_fly(&mut self, camera: &Camera, flight: Flight) {
+        *self = CameraMode::FreeFly(FreeFly::from_camera(camera, flight));
+    }
+
+    pub fn enter_follow(&mut self, follow: Follow) {
+        *self = CameraMode::Follow(follow);
+    }
+
+    pub fn enter_path(&mut self, playback: Playback) {
+        *self = CameraMode::Path(playback);
+    }
+}
@@ -0,0 +1,350 @@
+use std::fs;
+use std::ops::{Add, Mul, Sub};
+use std::time::Instant;
+use nalgebra_glm::{DVec3, Vec3};
+use crate::camera::DEFAULT_FOV;
+use crate::settings;
+use crate::viewpoint::Viewpoint;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    CatmullRom, // Pasa por todos los cuadros clave con tangentes automáticas
+    Bezier,     // Igual, pero cada tramo puede llevar sus propios puntos de control
+}
+
+impl Interpolation {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "catmull-rom" => Some(Interpolation::CatmullRom),
+            "bezier" => Some(Interpolation::Bezier),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    pub time: f32, // Segundos desde el comienzo del recorrido
+    pub eye: DVec3,
+    pub target: DVec3,
+    pub up: Vec3,
+    pub fov: f32, // Vertical, en radianes
+}
+
+// Puntos de control de un tramo de Bézier para el ojo y el objetivo
+#[derive(Debug, Clone, Copy)]
+struct Controls {
+    eye: [DVec3; 2],
+    target: [DVec3; 2],
+}
+
+// Recorrido de cámara por cuadros clave. Se lee de un archivo de texto:
+//
+//   interpolation = catmull-rom          # o bezier
+//   # tiempo  ojo        objetivo  [arriba]  [fov en grados]
+//   0         0,9,15     0,0,0     0,1,0     45
+//   control   4,9,12     0,0,0               # solo bezier: dos líneas por tramo,
+//   control   8,6,8      2,0,0               # ojo y objetivo de cada punto de control
+//   4.5       10,3,5     3,0,0
+//
+// Los vectores se escriben sin espacios. En los extremos la cámara arranca y frena con velocidad cero.
+pub struct CameraPath {
+    pub interpolation: Interpolation,
+    keyframes: Vec<Keyframe>,
+    controls: Vec<Option<Controls>>, // Uno por tramo, entre el cuadro clave i y el i + 1
+}
+
+impl CameraPath {
+    pub fn load(filename: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        CameraPath::parse(&contents).map_err(|e| format!("{}:{}", filename, e))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut interpolation = Interpolation::CatmullRom;
+        let mut keyframes: Vec<Keyframe> = Vec::new();
+        let mut controls: Vec<Option<Controls>> = Vec::new();
+        let mut pending: Vec<(DVec3, DVec3)> = Vec::new(); // Puntos de control desde el último cuadro clave
+
+        for (number, line) in contents.lines().enumerate() {
+            let error = |message: &str| format!("{}: {}", number + 1, message);
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                match key.trim() {
+                    "interpolation" => {
+                        interpolation = Interpolation::parse(value).ok_or_else(|| error("interpolación desconocida"))?;
+                    }
+                    other => return Err(error(&format!("opción desconocida `{}`", other))),
+                }
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields[0] == "control" {
+                if keyframes.is_empty() || fields.len() != 3 {
+                    return Err(error("se esperaba `control ojo objetivo` después de un cuadro clave"));
+                }
+                pending.push((parse_vec3(fields[1]).map_err(|e| error(&e))?, parse_vec3(fields[2]).map_err(|e| error(&e))?));
+                continue;
+            }
+
+            if !(3..=5).contains(&fields.len()) {
+                return Err(error("se esperaba `tiempo ojo objetivo [arriba] [fov]`"));
+            }
+            let time: f32 = fields[0].parse().map_err(|_| error("tiempo inválido"))?;
+            if keyframes.last().is_some_and(|last| time <= last.time) {
+                return Err(error("los tiempos deben ser crecientes"));
+            }
+            let up = match fields.get(3) {
+                Some(field) => parse_vec3(field).map_err(|e| error(&e))?.cast(),
+                None => Vec3::y(),
+            };
+            let fov = match fields.get(4) {
+                Some(field) => field.parse::<f32>().map_err(|_| error("fov inválido"))?.to_radians(),
+                None => DEFAULT_FOV,
+            };
+            if !keyframes.is_empty() {
+                controls.push(take_controls(&mut pending).map_err(|e| error(&e))?);
+            }
+            keyframes.push(Keyframe {
+                time,
+                eye: parse_vec3(fields[1]).map_err(|e| error(&e))?,
+                target: parse_vec3(fields[2]).map_err(|e| error(&e))?,
+                up,
+                fov,
+            });
+        }
+
+        // Los errores del recorrido completo se informan en la última línea
+        let end = contents.lines().count();
+        if keyframes.len() < 2 {
+            return Err(format!("{}: un recorrido necesita al menos dos cuadros clave", end));
+        }
+        if !pending.is_empty() {
+            return Err(format!("{}: puntos de control después del último cuadro clave", end));
+        }
+        if interpolation == Interpolation::CatmullRom && controls.iter().any(Option::is_some) {
+            return Err(format!("{}: los puntos de control solo sirven con `interpolation = bezier`", end));
+        }
+        Ok(CameraPath { interpolation, keyframes, controls })
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes[self.keyframes.len() - 1].time - self.keyframes[0].time
+    }
+
+    // Vista a los `t` segundos del comienzo; fuera del recorrido se queda en los extremos
+    pub fn sample(&self, t: f32) -> Viewpoint {
+        let keys = &self.keyframes;
+        let time = (keys[0].time + t).clamp(keys[0].time, keys[keys.len() - 1].time);
+        let segment = keys.windows(2).position(|pair| time <= pair[1].time).unwrap_or(keys.len() - 2);
+        let (a, b) = (&keys[segment], &keys[segment + 1]);
+        // La curva se evalúa en f64, como las posiciones del mundo
+        let h = (b.time - a.time) as f64;
+        let u = (time - a.time) as f64 / h;
+
+        let spline = |get: &dyn Fn(&Keyframe) -> DVec3| {
+            hermite(get(a), self.tangent(segment, get), get(b), self.tangent(segment + 1, get), h, u)
+        };
+        let (eye, target) = match self.interpolation {
+            Interpolation::CatmullRom => (spline(&|key| key.eye), spline(&|key| key.target)),
+            Interpolation::Bezier => {
+                let controls = self.controls[segment].unwrap_or_else(|| self.automatic_controls(segment));
+                (
+                    bezier(a.eye, controls.eye[0], controls.eye[1], b.eye, u),
+                    bezier(a.target, controls.target[0], controls.target[1], b.target, u),
+                )
+            }
+        };
+        let up = spline(&|key| key.up.cast()).cast::<f32>().try_normalize(1e-6).unwrap_or(a.up);
+        let fov = |key: &Keyframe| key.fov as f64;
+        let fov = hermite(fov(a), self.tangent(segment, &fov), fov(b), self.tangent(segment + 1, &fov), h, u) as f32;
+
+        Viewpoint { eye, center: target, up, fov: fov.max(1e-3) }
+    }
+
+    // Puntos de control de un tramo de Bézier que no trae los suyos: a un tercio del tramo sobre las
+    // tangentes de Catmull-Rom, así que la curva es la misma que con `interpolation = catmull-rom`
+    fn automatic_controls(&self, segment: usize) -> Controls {
+        let (a, b) = (&self.keyframes[segment], &self.keyframes[segment + 1]);
+        let third = (b.time - a.time) as f64 / 3.0;
+        let controls = |get: &dyn Fn(&Keyframe) -> DVec3| {
+            [get(a) + self.tangent(segment, get) * third, get(b) - self.tangent(segment + 1, get) * third]
+        };
+        Controls { eye: controls(&|key| key.eye), target: controls(&|key| key.target) }
+    }
+
+    // Velocidad por segundo en el cuadro clave `index`, como en Catmull-Rom con tiempos no uniformes
+    fn tangent<T>(&self, index: usize, get: &dyn Fn(&Keyframe) -> T) -> T
+    where
+        T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+    {
+        let keys = &self.keyframes;
+        if index == 0 || index == keys.len() - 1 {
+            return get(&keys[index]) * 0.0;
+        }
+        let (previous, next) = (&keys[index - 1], &keys[index + 1]);
+        (get(next) - get(previous)) * (1.0 / (next.time - previous.time) as f64)
+    }
+}
+
+// Reproducción en vivo, con el reloj de pared
+pub struct Playback {
+    start: Instant,
+    pub duration: f32,
+}
+
+impl Playback {
+    pub fn start(path: &CameraPath) -> Self {
+        Playback { start: Instant::now(), duration: path.duration() }
+    }
+
+    pub fn time(&self) -> f32 {
+        self.start.elapsed().as_secs_f32()
+    }
+}
+
+fn take_controls(pending: &mut Vec<(DVec3, DVec3)>) -> Result<Option<Controls>, String> {
+    let controls = match pending.as_slice() {
+        [] => None,
+        [first, second] => Some(Controls { eye: [first.0, second.0], target: [first.1, second.1] }),
+        _ => return Err("cada tramo lleva cero o dos puntos de control".to_string()),
+    };
+    pending.clear();
+    Ok(controls)
+}
+
+fn parse_vec3(field: &str) -> Result<DVec3, String> {
+    settings::parse_dvec3(field).ok_or_else(|| format!("vector inválido `{}`: se esperaban tres números separados por comas", field))
+}
+
+// Hermite cúbica en el tramo de duración `h`, con las tangentes en unidades por segundo
+fn hermite<T>(p0: T, m0: T, p1: T, m1: T, h: f64, u: f64) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+{
+    let (u2, u3) = (u * u, u * u * u);
+    p0 * (2.0 * u3 - 3.0 * u2 + 1.0) + m0 * ((u3 - 2.0 * u2 + u) * h) + p1 * (3.0 * u2 - 2.0 * u3) + m1 * ((u3 - u2) * h)
+}
+
+fn bezier(p0: DVec3, c0: DVec3, c1: DVec3, p1: DVec3, u: f64) -> DVec3 {
+    let v = 1.0 - u;
+    p0 * (v * v * v) + c0 * (3.0 * v * v * u) + c1 * (3.0 * v * u * u) + p1 * (u * u * u)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CATMULL_ROM: &str = "\
+# Tres cuadros clave con tiempos desiguales
+0    0,9,15   0,0,0
+2    10,3,5   3,0,0   0,1,0  30
+5    -4,2,0   0,1,0   1,0,0
+";
+
+    const BEZIER: &str = "\
+interpolation = bezier
+0        0,0,0    0,0,-1
+control  1,2,0    0,0,-1
+control  3,2,0    4,0,-1
+4        4,0,0    4,0,-1
+8        8,0,0    8,0,-1
+";
+
+    fn error(contents: &str) -> String {
+        CameraPath::parse(contents).err().expect("el recorrido no debería aceptarse")
+    }
+
+    fn assert_near(value: DVec3, expected: DVec3) {
+        assert!((value - expected).magnitude() < 1e-9, "{value:?} en vez de {expected:?}");
+    }
+
+    #[test]
+    fn rejects_malformed_paths() {
+        assert_eq!(error("0 0,0,0 0,0,0\n1 1,0,0 0,0,0\n1 2,0,0 0,0,0\n"), "3: los tiempos deben ser crecientes");
+        assert_eq!(error("0 0,0,0 0,0,0\n-1 1,0,0 0,0,0\n"), "2: los tiempos deben ser crecientes");
+        assert!(error("interpolation = bezier\ncontrol 1,0,0 0,0,0\n0 0,0,0 0,0,0\n").starts_with("2: se esperaba `control"));
+        assert_eq!(
+            error("interpolation = bezier\n0 0,0,0 0,0,0\n1 1,0,0 0,0,0\ncontrol 1,0,0 0,0,0\ncontrol 2,0,0 0,0,0\n"),
+            "5: puntos de control después del último cuadro clave"
+        );
+        assert_eq!(
+            error("0 0,0,0 0,0,0\ncontrol 1,0,0 0,0,0\ncontrol 2,0,0 0,0,0\n1 1,0,0 0,0,0\n"),
+            "4: los puntos de control solo sirven con `interpolation = bezier`"
+        );
+        assert_eq!(
+            error("interpolation = bezier\n0 0,0,0 0,0,0\ncontrol 1,0,0 0,0,0\n1 1,0,0 0,0,0\n"),
+            "4: cada tramo lleva cero o dos puntos de control"
+        );
+        assert_eq!(error("0 0,0,0 0,0,0\n"), "1: un recorrido necesita al menos dos cuadros clave");
+        assert_eq!(error("interpolation = lineal\n"), "1: interpolación desconocida");
+        assert_eq!(error("velocidad = 2\n"), "1: opción desconocida `velocidad`");
+        assert!(error("0 0,0 0,0,0\n1 1,0,0 0,0,0\n").starts_with("1: vector inválido `0,0`"));
+        assert!(error("0 0,0,0\n").starts_with("1: se esperaba `tiempo"));
+    }
+
+    #[test]
+    fn passes_through_the_keyframes() {
+        for contents in [CATMULL_ROM, BEZIER] {
+            let path = CameraPath::parse(contents).unwrap();
+            let start = path.keyframes()[0].time;
+            for key in path.keyframes() {
+                let view = path.sample(key.time - start);
+                assert_near(view.eye, key.eye);
+                assert_near(view.center, key.target);
+                assert!((view.fov - key.fov).abs() < 1e-6);
+                assert!((view.up - key.up).magnitude() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn clamps_outside_the_path_and_eases_at_the_ends() {
+        let path = CameraPath::parse(CATMULL_ROM).unwrap();
+        assert_eq!(path.duration(), 5.0);
+        let keys = path.keyframes();
+        assert_near(path.sample(-1.0).eye, keys[0].eye);
+        assert_near(path.sample(60.0).eye, keys[2].eye);
+        // Con velocidad cero en los extremos, el primer tramo arranca como u² y no como u
+        let early = path.sample(0.02).eye - keys[0].eye;
+        assert!(early.magnitude() < 0.01 * (keys[1].eye - keys[0].eye).magnitude());
+        assert_eq!(keys[0].up, Vec3::y());
+        assert_eq!(keys[0].fov, DEFAULT_FOV);
+        assert!((keys[1].fov - 30f32.to_radians()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn catmull_rom_tangents_follow_the_neighbours() {
+        let path = CameraPath::parse(CATMULL_ROM).unwrap();
+        let keys = path.keyframes();
+        // En el cuadro clave del medio la velocidad es (p2 - p0) / (t2 - t0)
+        let dt = 1e-4;
+        let velocity = (path.sample(2.0 + dt as f32).eye - path.sample(2.0 - dt as f32).eye) / (2.0 * dt);
+        let expected = (keys[2].eye - keys[0].eye) / 5.0;
+        assert!((velocity - expected).magnitude() < 1e-2, "{velocity:?} en vez de {expected:?}");
+    }
+
+    #[test]
+    fn bezier_uses_its_control_points() {
+        let path = CameraPath::parse(BEZIER).unwrap();
+        // En la mitad del tramo la cúbica vale (p0 + 3 c0 + 3 c1 + p1) / 8
+        let view = path.sample(2.0);
+        assert_near(view.eye, DVec3::new(2.0, 1.5, 0.0));
+        assert_near(view.center, DVec3::new(2.0, 0.0, -1.0));
+
+        // Un tramo sin puntos de control sigue la misma curva que Catmull-Rom
+        let catmull_rom = CameraPath::parse(&BEZIER.replace("interpolation = bezier", "").replace("control", "# control")).unwrap();
+        for t in [4.5, 5.0, 6.3, 7.9] {
+            assert_near(path.sample(t).eye, catmull_rom.sample(t).eye);
+            assert_near(path.sample(t).center, catmull_rom.sample(t).center);
+        }
+    }
+}
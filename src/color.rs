@@ -1,53 +1,105 @@
 use std::fmt;
+use std::sync::OnceLock;
 
+// Color en espacio lineal con canales de punto flotante. No se satura: los valores mayores
+// a 1.0 (el sol, la lava) se conservan hasta el mapeo de tonos al presentar el cuadro.
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
-  r: u8,
-  g: u8,
-  b: u8,
+  pub r: f32,
+  pub g: f32,
+  pub b: f32,
 }
 
 impl Color {
-  // Constructor to initialize the color using r, g, b values as u8
+  // Constructor from 8-bit sRGB values, decoded to linear space
   pub fn new(r: u8, g: u8, b: u8) -> Self {
-    Color { r, g, b }
+    let table = srgb_to_linear_table();
+    Color {
+      r: table[r as usize],
+      g: table[g as usize],
+      b: table[b as usize],
+    }
   }
 
   // default color
   pub fn black() -> Self {
-    Color { r: 0, g: 0, b: 0 }
+    Color { r: 0.0, g: 0.0, b: 0.0 }
   }
 
-  // New constructor to initialize the color using r, g, b values as f32 (0.0 to 1.0)
+  // Constructor from linear values; anything above 1.0 is kept as HDR
   pub fn from_float(r: f32, g: f32, b: f32) -> Self {
     Color {
-      r: (r.clamp(0.0, 1.0) * 255.0) as u8,
-      g: (g.clamp(0.0, 1.0) * 255.0) as u8,
-      b: (b.clamp(0.0, 1.0) * 255.0) as u8,
+      r: r.max(0.0),
+      g: g.max(0.0),
+      b: b.max(0.0),
     }
   }
 
-  // Function to create a color from a hex value
+  // Function to create a color from an sRGB hex value
   pub fn from_hex(hex: u32) -> Self {
     let r = ((hex >> 16) & 0xFF) as u8;
     let g = ((hex >> 8) & 0xFF) as u8;
     let b = (hex & 0xFF) as u8;
-    Color { r, g, b }
+    Color::new(r, g, b)
+  }
+
+  // Function to return the color as an sRGB hex value (clamps to the displayable range)
+  pub fn to_hex(self) -> u32 {
+    let r = linear_to_srgb(self.r) as u32;
+    let g = linear_to_srgb(self.g) as u32;
+    let b = linear_to_srgb(self.b) as u32;
+    (r << 16) | (g << 8) | b
   }
 
-  // Function to return the color as a hex value
-  pub fn to_hex(&self) -> u32 {
-    ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
+  // Perceived brightness (Rec. 709 weights)
+  pub fn luminance(&self) -> f32 {
+    0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
   }
 
   // Linear interpolation between two colors
   pub fn lerp(&self, other: &Color, t: f32) -> Self {
     let t = t.clamp(0.0, 1.0);
     Color {
-      r: (self.r as f32 + (other.r as f32 - self.r as f32) * t).round() as u8,
-      g: (self.g as f32 + (other.g as f32 - self.g as f32) * t).round() as u8,
-      b: (self.b as f32 + (other.b as f32 - self.b as f32) * t).round() as u8,
+      r: self.r + (other.r - self.r) * t,
+      g: self.g + (other.g - self.g) * t,
+      b: self.b + (other.b - self.b) * t,
+    }
+  }
+}
+
+// 8-bit sRGB decoding is precomputed; the shaders build many constant colors per fragment
+fn srgb_to_linear_table() -> &'static [f32; 256] {
+  static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+  TABLE.get_or_init(|| {
+    let mut table = [0.0; 256];
+    for (i, value) in table.iter_mut().enumerate() {
+      *value = decode_srgb(i as f32 / 255.0);
     }
+    table
+  })
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+  (encode_srgb(value) * 255.0).round() as u8
+}
+
+// sRGB transfer function for a single channel in [0, 1]
+pub fn encode_srgb(value: f32) -> f32 {
+  let c = value.clamp(0.0, 1.0);
+  if c <= 0.0031308 {
+    c * 12.92
+  } else {
+    1.055 * c.powf(1.0 / 2.4) - 0.055
+  }
+}
+
+// Inverse of `encode_srgb`
+pub fn decode_srgb(value: f32) -> f32 {
+  let c = value.clamp(0.0, 1.0);
+  if c <= 0.04045 {
+    c / 12.92
+  } else {
+    ((c + 0.055) / 1.055).powf(2.4)
   }
 }
 
@@ -59,9 +111,9 @@ impl Add for Color {
 
   fn add(self, other: Color) -> Color {
     Color {
-      r: self.r.saturating_add(other.r),
-      g: self.g.saturating_add(other.g),
-      b: self.b.saturating_add(other.b),
+      r: self.r + other.r,
+      g: self.g + other.g,
+      b: self.b + other.b,
     }
   }
 }
@@ -74,9 +126,22 @@ impl Mul<f32> for Color {
 
   fn mul(self, scalar: f32) -> Color {
     Color {
-      r: (self.r as f32 * scalar).clamp(0.0, 255.0) as u8,
-      g: (self.g as f32 * scalar).clamp(0.0, 255.0) as u8,
-      b: (self.b as f32 * scalar).clamp(0.0, 255.0) as u8,
+      r: (self.r * scalar).max(0.0),
+      g: (self.g * scalar).max(0.0),
+      b: (self.b * scalar).max(0.0),
+    }
+  }
+}
+
+// Implement channel-wise multiplication (filtering one color by another)
+impl Mul<Color> for Color {
+  type Output = Color;
+
+  fn mul(self, other: Color) -> Color {
+    Color {
+      r: self.r * other.r,
+      g: self.g * other.g,
+      b: self.b * other.b,
     }
   }
 }
@@ -84,6 +149,32 @@ impl Mul<f32> for Color {
 // Implement display formatting for Color
 impl fmt::Display for Color {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
+    write!(f, "Color(r: {:.3}, g: {:.3}, b: {:.3})", self.r, self.g, self.b)
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn srgb_round_trip() {
+    for i in 0..=255u32 {
+      let value = i as f32 / 255.0;
+      assert!((encode_srgb(decode_srgb(value)) - value).abs() < 1e-5, "{value}");
+      let hex = i * 0x010101;
+      assert_eq!(Color::from_hex(hex).to_hex(), hex);
+    }
+  }
+
+  #[test]
+  fn srgb_transfer_function() {
+    assert_eq!(decode_srgb(0.0), 0.0);
+    assert!((encode_srgb(1.0) - 1.0).abs() < 1e-6);
+    assert!((decode_srgb(0.5) - 0.214_041).abs() < 1e-5);
+    // Los dos tramos de la curva se unen en el mismo punto
+    assert!((encode_srgb(0.003_130_8) - 0.040_45).abs() < 1e-5);
+    // Lo que cae fuera de [0, 1] se recorta al codificar
+    assert_eq!(Color::from_float(4.0, 0.5, 0.0).to_hex(), 0xFFBC00);
+  }
+}
@@ -1,14 +1,129 @@
-use fastnoise_lite::FastNoiseLite;
-use nalgebra_glm::Vec3;
+use nalgebra::UnitQuaternion;
+use nalgebra_glm::{DMat3, DVec3, Mat4, Vec3};
 use crate::vertex::Vertex;
+use crate::material::Material;
+use crate::atmosphere::Atmosphere;
+use crate::ring::Ring;
+use crate::color::Color;
+use crate::ephemeris::{self, OrbitalElements};
+
+// Margen sobre el radio dentro del cual un cuerpo del sistema ficticio deja de dibujarse, para no
+// llenar la pantalla cuando la cámara pasa a través de él
+pub const DISAPPEARANCE_BUFFER: f32 = 5.0;
+
+// Cómo se pasa la distancia real al cuerpo central, en km, a unidades de la escena
+#[derive(Debug, Clone, Copy)]
+pub enum DistanceScale {
+    Linear { km_per_unit: f64 },
+    // `base + factor * sqrt(r / reference)`: conserva el orden y la forma de las órbitas pero junta las lejanas
+    Compressed { reference: f64, base: f64, factor: f64 },
+}
+
+impl DistanceScale {
+    pub fn distance(&self, km: f64) -> f64 {
+        match *self {
+            DistanceScale::Linear { km_per_unit } => km / km_per_unit,
+            DistanceScale::Compressed { reference, base, factor } => base + factor * (km / reference).sqrt(),
+        }
+    }
+
+    // Escala un desplazamiento en km sin cambiar su dirección
+    pub fn apply(&self, offset: &DVec3) -> DVec3 {
+        let km = offset.magnitude();
+        if km == 0.0 {
+            return DVec3::zeros();
+        }
+        offset * (self.distance(km) / km)
+    }
+}
+
+pub enum Orbit {
+    // Circular en el plano eclíptico, con velocidad angular constante en radianes por día
+    Circular { radius: f64, phase: f64, speed: f64 },
+    // Elementos keplerianos; `frame` lleva del plano de referencia de los elementos a la eclíptica
+    Kepler { elements: OrbitalElements, frame: DMat3, scale: DistanceScale },
+}
+
+impl Orbit {
+    // Posición respecto del cuerpo central `days` días después de la época, en la escena
+    pub fn offset(&self, days: f64) -> DVec3 {
+        match self {
+            Orbit::Circular { radius, phase, speed } => {
+                let angle = days * speed + phase;
+                DVec3::new(radius * angle.cos(), 0.0, radius * angle.sin())
+            }
+            Orbit::Kepler { elements, frame, scale } => {
+                ephemeris::to_scene(&scale.apply(&(frame * elements.position(days))))
+            }
+        }
+    }
+
+    // Una vuelta completa como polilínea cerrada de `segments` tramos, respecto del cuerpo central.
+    // Los elementos keplerianos cambian muy despacio, así que se toman los de `days`.
+    pub fn path(&self, days: f64, segments: usize) -> Vec<DVec3> {
+        (0..=segments)
+            .map(|i| {
+                let angle = i as f64 / segments as f64 * std::f64::consts::TAU;
+                match self {
+                    Orbit::Circular { radius, .. } => DVec3::new(radius * angle.cos(), 0.0, radius * angle.sin()),
+                    Orbit::Kepler { elements, frame, scale } => {
+                        ephemeris::to_scene(&scale.apply(&(frame * elements.at(days).position_at(angle))))
+                    }
+                }
+            })
+            .collect()
+    }
+
+    // El sol del sistema ficticio tiene una órbita de radio cero
+    pub fn is_visible(&self) -> bool {
+        match self {
+            Orbit::Circular { radius, .. } => *radius > 0.0,
+            Orbit::Kepler { .. } => true,
+        }
+    }
+}
 
 pub struct Cuerpo {
     pub name: String,
-    pub translation: Vec3,
-    pub rotation: Vec3,
     pub scale: f32,
     pub vertex_array: Vec<Vertex>, // Vertices del modelo 3D del planeta
-    pub orbit_radius: f32,        // Radio de la órbita del planeta
-    pub phase_offset: f32,
-    pub rotation_speed: f32,
-}
\ No newline at end of file
+    pub orbit: Orbit,
+    pub parent: Option<usize>,    // Cuerpo alrededor del cual orbita; sin él, el origen
+    pub pole: Vec3,               // Eje de rotación en la escena
+    pub rotation_speed: f32,      // Radianes por día; negativa si gira al revés respecto de `pole`
+    pub shader: usize,            // Cuál de los shaders de superficie usa
+    pub material: Material,       // Parámetros de superficie (relieve)
+    pub atmosphere: Option<Atmosphere>,
+    pub ring: Option<Ring>,
+    pub color: Color,             // Color representativo para órbitas, estelas y etiquetas
+    pub disappearance_buffer: f32, // Margen sobre el radio dentro del cual deja de dibujarse
+}
+
+impl Cuerpo {
+    // Lleva el eje y del modelo al polo
+    pub fn orientation(&self) -> Mat4 {
+        UnitQuaternion::rotation_between(&Vec3::y(), &self.pole)
+            .unwrap_or_else(|| UnitQuaternion::from_axis_angle(&Vec3::x_axis(), std::f32::consts::PI))
+            .to_homogeneous()
+    }
+
+    // Rotación sobre su propio eje. El ángulo se reduce en f64, porque los días desde la época pueden ser muchos.
+    pub fn spin_matrix(&self, days: f64) -> Mat4 {
+        let angle = (days * self.rotation_speed as f64).rem_euclid(std::f64::consts::TAU) as f32;
+        self.orientation() * Mat4::from_axis_angle(&Vec3::y_axis(), angle)
+    }
+
+    // Traslación relativa al ojo (ya restada en f64), rotación propia y escala
+    pub fn model_matrix(&self, translation: Vec3, days: f64) -> Mat4 {
+        Mat4::new_translation(&translation) * self.spin_matrix(days) * Mat4::new_scaling(self.scale)
+    }
+
+    // Radio de la esfera que envuelve la malla en el mundo
+    pub fn bounding_radius(&self) -> f32 {
+        self.vertex_array.iter().map(|v| v.position.magnitude()).fold(0.0, f32::max) * self.scale
+    }
+
+    pub fn is_too_close(&self, position: Vec3, eye: Vec3) -> bool {
+        (eye - position).magnitude() <= self.scale + self.disappearance_buffer
+    }
+}
@@ -0,0 +1,96 @@
+use crate::color::{decode_srgb, Color};
+use crate::fragment::Fragment;
+use crate::shaders::sun_intensity;
+use crate::Uniforms;
+
+// Vistas de depuración del pipeline; todas salvo `Shaded` y `Wireframe` reemplazan el sombreado
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugView {
+    Shaded,    // Render normal
+    Wireframe, // Aristas de las caras frontales sobre el render normal
+    Flat,      // Una normal por triángulo, sin texturas procedurales
+    Normals,   // Normal en el mundo como color: (n + 1) / 2
+    Depth,     // Distancia a la cámara, normalizada entre lo más cercano y lo más lejano del cuadro
+    UvChecker, // Tablero de ajedrez sobre las coordenadas de textura
+    Overdraw,  // Cantidad de fragmentos que recibió cada pixel, incluidos los que fallan la profundidad
+}
+
+// Orden de las teclas F1-F6
+pub const VIEWS: [DebugView; 6] = [
+    DebugView::Wireframe,
+    DebugView::Flat,
+    DebugView::Normals,
+    DebugView::Depth,
+    DebugView::UvChecker,
+    DebugView::Overdraw,
+];
+
+impl DebugView {
+    // Acepta `shaded`, `wireframe`, `flat`, `normals`, `depth`, `uv` y `overdraw`
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        std::iter::once(DebugView::Shaded).chain(VIEWS).find(|view| view.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DebugView::Shaded => "shaded",
+            DebugView::Wireframe => "wireframe",
+            DebugView::Flat => "flat",
+            DebugView::Normals => "normals",
+            DebugView::Depth => "depth",
+            DebugView::UvChecker => "uv",
+            DebugView::Overdraw => "overdraw",
+        }
+    }
+
+    // Si se usan los shaders de la escena, el cielo, el bloom, el mapeo de tonos y el postproceso
+    pub fn is_shaded(&self) -> bool {
+        matches!(self, DebugView::Shaded | DebugView::Wireframe)
+    }
+
+    // Anillos y atmósferas se omiten en las vistas que muestran atributos de la malla opaca
+    pub fn draws_translucent(&self) -> bool {
+        matches!(self, DebugView::Shaded | DebugView::Wireframe | DebugView::Overdraw)
+    }
+
+    // Color de depuración de un fragmento opaco, iluminado desde el sol como el render normal.
+    // La profundidad se pinta después, a partir del zbuffer.
+    pub fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+        match self {
+            DebugView::Flat => {
+                let gray = 0.15 + 0.85 * sun_intensity(fragment, uniforms);
+                display(gray, gray, gray)
+            }
+            DebugView::Normals => {
+                let n = fragment.normal;
+                display((n.x + 1.0) * 0.5, (n.y + 1.0) * 0.5, (n.z + 1.0) * 0.5)
+            }
+            DebugView::UvChecker => {
+                let (u, v) = (fragment.tex_coords.x, fragment.tex_coords.y);
+                let cell = ((u * 16.0).floor() + (v * 8.0).floor()) as i64;
+                let base = if cell.rem_euclid(2) == 0 { 0.85 } else { 0.25 };
+                let shade = base * (0.3 + 0.7 * sun_intensity(fragment, uniforms));
+                display(shade, shade * 0.9 + 0.05, shade * 0.8)
+            }
+            _ => Color::black(),
+        }
+    }
+}
+
+// Color que se ve en pantalla exactamente con estos valores, sin la curva sRGB de `present`
+pub fn display(r: f32, g: f32, b: f32) -> Color {
+    Color::from_float(decode_srgb(r), decode_srgb(g), decode_srgb(b))
+}
+
+// Escala de calor logarítmica para el overdraw: 1 fragmento azul, 2 verde, 4 amarillo, 8 rojo, 16 o más blanco
+pub fn heat_color(count: u16) -> Color {
+    const STOPS: [(u8, u8, u8); 6] = [(0, 0, 0), (30, 60, 200), (40, 180, 80), (240, 220, 40), (230, 50, 30), (255, 255, 255)];
+    if count == 0 {
+        return Color::black();
+    }
+    let t = ((count as f32).log2() + 1.0).min((STOPS.len() - 1) as f32);
+    let index = (t.floor() as usize).min(STOPS.len() - 2);
+    let (a, b) = (STOPS[index], STOPS[index + 1]);
+    Color::new(a.0, a.1, a.2).lerp(&Color::new(b.0, b.1, b.2), t - index as f32)
+}
@@ -0,0 +1,101 @@
+use nalgebra_glm::Vec2;
+
+// Cómo se reparte la precisión del zbuffer entre el plano cercano y el lejano. En los tres modos
+// el valor guardado crece con la distancia, así que la prueba de profundidad es siempre `menor pasa`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthMode {
+    Standard,    // z en NDC: casi toda la precisión queda pegada al plano cercano
+    Reversed,    // -near / w: el flotante es más denso cerca de cero, o sea lejos de la cámara
+    Logarithmic, // log(w / near) / log(far / near): error relativo parejo en todo el rango
+}
+
+impl DepthMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "standard" => Some(DepthMode::Standard),
+            "reversed" => Some(DepthMode::Reversed),
+            "logarithmic" => Some(DepthMode::Logarithmic),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DepthMode::Standard => "estándar",
+            DepthMode::Reversed => "Z invertida",
+            DepthMode::Logarithmic => "logarítmica",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DepthMode::Standard => DepthMode::Reversed,
+            DepthMode::Reversed => DepthMode::Logarithmic,
+            DepthMode::Logarithmic => DepthMode::Standard,
+        }
+    }
+}
+
+// Planos de la proyección y modo del zbuffer: todo lo necesario para pasar de la distancia a la
+// cámara (la w del espacio de recorte) al valor guardado y de vuelta
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthRange {
+    pub near: f32,
+    pub far: f32,
+    pub mode: DepthMode,
+}
+
+impl Default for DepthRange {
+    fn default() -> Self {
+        DepthRange { near: 0.1, far: 1000.0, mode: DepthMode::Standard }
+    }
+}
+
+impl DepthRange {
+    // Profundidad que sale del vertex shader. Es afín en 1 / w, así que el rasterizador puede
+    // interpolarla linealmente en pantalla. El modo logarítmico parte de la misma que Z invertida y
+    // toma el logaritmo por fragmento en `fragment_depth`; hacerlo por vértice deformaría los
+    // triángulos grandes, como los anillos vistos de canto.
+    pub fn vertex_depth(&self, w: f32) -> f32 {
+        let (near, far) = (self.near, self.far);
+        match self.mode {
+            DepthMode::Standard => (far + near) / (far - near) - 2.0 * far * near / ((far - near) * w),
+            DepthMode::Reversed | DepthMode::Logarithmic => -near / w,
+        }
+    }
+
+    // Valor que se guarda en el zbuffer a partir del interpolado, junto con su derivada en pantalla
+    // para las muestras de MSAA
+    pub fn fragment_depth(&self, depth: f32, slope: Vec2) -> (f32, Vec2) {
+        match self.mode {
+            DepthMode::Standard | DepthMode::Reversed => (depth, slope),
+            DepthMode::Logarithmic => {
+                // depth = -near / w, así que w / near = -1 / depth
+                let depth = depth.min(-f32::MIN_POSITIVE);
+                let scale = 1.0 / (self.far / self.near).ln();
+                (-(-depth).ln() * scale, slope * (-scale / depth))
+            }
+        }
+    }
+
+    // Valor guardado para un punto a distancia `w`, para las líneas y etiquetas que no pasan por el rasterizador
+    pub fn stored(&self, w: f32) -> f32 {
+        self.fragment_depth(self.vertex_depth(w), Vec2::zeros()).0
+    }
+
+    // Distancia a la cámara a lo largo de la vista a partir del valor guardado
+    pub fn distance(&self, stored: f32) -> f32 {
+        let (near, far) = (self.near, self.far);
+        match self.mode {
+            DepthMode::Standard => 2.0 * near * far / (far + near - stored * (far - near)),
+            DepthMode::Reversed => -near / stored,
+            DepthMode::Logarithmic => near * (stored * (far / near).ln()).exp(),
+        }
+    }
+
+    // Acerca a la cámara un valor guardado una fracción de su distancia, para que una línea pase la
+    // prueba de profundidad contra su propia superficie
+    pub fn nudge(&self, stored: f32, fraction: f32) -> f32 {
+        self.stored(self.distance(stored) * (1.0 - fraction))
+    }
+}
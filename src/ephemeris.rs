@@ -0,0 +1,112 @@
+use nalgebra_glm::{DMat3, DVec3};
+
+// Época J2000.0 (1 de enero de 2000, 12:00 TDB) en días julianos
+pub const J2000: f64 = 2451545.0;
+pub const DAYS_PER_CENTURY: f64 = 36525.0;
+pub const AU_KM: f64 = 149_597_870.7;
+// Oblicuidad de la eclíptica en J2000, en grados
+pub const OBLIQUITY: f64 = 23.439_291_1;
+
+// Elementos keplerianos. Las distancias van en km y los ángulos en grados, como en las tablas.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Elements {
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    pub mean_longitude: f64,
+    pub longitude_of_periapsis: f64,
+    pub longitude_of_node: f64,
+}
+
+// Elementos medios en J2000 y su variación por siglo juliano. Los ángulos se miden en el plano de
+// referencia de la órbita: la eclíptica para los planetas, el ecuador del planeta para casi todas las lunas.
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitalElements {
+    pub epoch: Elements,
+    pub rate: Elements,
+}
+
+impl OrbitalElements {
+    // Elementos `days` días después de J2000
+    pub fn at(&self, days: f64) -> Elements {
+        let t = days / DAYS_PER_CENTURY;
+        let (epoch, rate) = (&self.epoch, &self.rate);
+        Elements {
+            semi_major_axis: epoch.semi_major_axis + rate.semi_major_axis * t,
+            eccentricity: epoch.eccentricity + rate.eccentricity * t,
+            inclination: epoch.inclination + rate.inclination * t,
+            mean_longitude: epoch.mean_longitude + rate.mean_longitude * t,
+            longitude_of_periapsis: epoch.longitude_of_periapsis + rate.longitude_of_periapsis * t,
+            longitude_of_node: epoch.longitude_of_node + rate.longitude_of_node * t,
+        }
+    }
+
+    // Posición en km en el plano de referencia, `days` días después de J2000
+    pub fn position(&self, days: f64) -> DVec3 {
+        let elements = self.at(days);
+        let mean_anomaly = (elements.mean_longitude - elements.longitude_of_periapsis).to_radians();
+        elements.position_at(eccentric_anomaly(mean_anomaly, elements.eccentricity))
+    }
+}
+
+impl Elements {
+    // Punto de la elipse para la anomalía excéntrica `anomaly` (radianes)
+    pub fn position_at(&self, anomaly: f64) -> DVec3 {
+        let (a, e) = (self.semi_major_axis, self.eccentricity);
+        let x = a * (anomaly.cos() - e);
+        let y = a * (1.0 - e * e).sqrt() * anomaly.sin();
+
+        let node = self.longitude_of_node.to_radians();
+        let periapsis = (self.longitude_of_periapsis - self.longitude_of_node).to_radians();
+        let inclination = self.inclination.to_radians();
+        let (sin_w, cos_w) = periapsis.sin_cos();
+        let (sin_n, cos_n) = node.sin_cos();
+        let (sin_i, cos_i) = inclination.sin_cos();
+        DVec3::new(
+            (cos_w * cos_n - sin_w * sin_n * cos_i) * x + (-sin_w * cos_n - cos_w * sin_n * cos_i) * y,
+            (cos_w * sin_n + sin_w * cos_n * cos_i) * x + (-sin_w * sin_n + cos_w * cos_n * cos_i) * y,
+            sin_w * sin_i * x + cos_w * sin_i * y,
+        )
+    }
+}
+
+// Resuelve la ecuación de Kepler M = E - e sen E por Newton
+pub fn eccentric_anomaly(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    let m = (mean_anomaly + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI;
+    let mut anomaly = if eccentricity < 0.8 { m } else { std::f64::consts::PI.copysign(m) };
+    for _ in 0..50 {
+        let step = (anomaly - eccentricity * anomaly.sin() - m) / (1.0 - eccentricity * anomaly.cos());
+        anomaly -= step;
+        if step.abs() < 1e-14 {
+            break;
+        }
+    }
+    anomaly
+}
+
+// Del ecuador terrestre (ICRF) a la eclíptica de J2000
+pub fn equatorial_to_ecliptic(v: &DVec3) -> DVec3 {
+    let (sin_e, cos_e) = OBLIQUITY.to_radians().sin_cos();
+    DVec3::new(v.x, v.y * cos_e + v.z * sin_e, -v.y * sin_e + v.z * cos_e)
+}
+
+// Dirección en la eclíptica de un polo dado por ascensión recta y declinación en grados
+pub fn pole_direction(right_ascension: f64, declination: f64) -> DVec3 {
+    let (ra, dec) = (right_ascension.to_radians(), declination.to_radians());
+    equatorial_to_ecliptic(&DVec3::new(dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin()))
+}
+
+// Del plano ecuatorial de un cuerpo con polo `pole` a la eclíptica: el eje x apunta al nodo ascendente
+// del ecuador sobre la eclíptica y el z al polo
+pub fn equator_frame(pole: &DVec3) -> DMat3 {
+    let z = pole.normalize();
+    let x = DVec3::z().cross(&z).try_normalize(1e-12).unwrap_or(DVec3::x());
+    let y = z.cross(&x);
+    DMat3::from_columns(&[x, y, z])
+}
+
+// De la eclíptica a la escena: el plano de las órbitas es el XZ y el norte de la eclíptica es +y.
+// Así, visto desde arriba, los planetas giran en sentido antihorario como en el cielo.
+pub fn to_scene(v: &DVec3) -> DVec3 {
+    DVec3::new(v.x, v.z, -v.y)
+}
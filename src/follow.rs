@@ -0,0 +1,98 @@
+use nalgebra::UnitQuaternion;
+use nalgebra_glm::DVec3;
+use crate::camera::Camera;
+use crate::cuerpo::Cuerpo;
+use crate::viewpoint::{Transition, Viewpoint, Viewpoints};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceFrame {
+    Inertial,   // La cámara solo se traslada con el cuerpo; las estrellas quedan quietas
+    CoRotating, // Además gira con la órbita, así la dirección al sol no cambia en pantalla
+}
+
+impl ReferenceFrame {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "inertial" => Some(ReferenceFrame::Inertial),
+            "corotating" => Some(ReferenceFrame::CoRotating),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReferenceFrame::Inertial => "inercial",
+            ReferenceFrame::CoRotating => "corrotante",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ReferenceFrame::Inertial => ReferenceFrame::CoRotating,
+            ReferenceFrame::CoRotating => ReferenceFrame::Inertial,
+        }
+    }
+}
+
+// Seguimiento de un cuerpo: el centro de la cámara queda sobre él y el ojo se mueve con él, de modo
+// que orbitar y hacer zoom siguen funcionando alrededor del cuerpo mientras avanza por su órbita
+pub struct Follow {
+    pub target: usize,
+    pub frame: ReferenceFrame,
+    last_radial: DVec3, // Del cuerpo central al cuerpo seguido, para el marco corrotante
+    // Mientras la cámara llega al cuerpo; su destino se mueve con él en cada cuadro
+    approach: Option<Transition>,
+}
+
+impl Follow {
+    // Lleva la cámara hasta el cuerpo con una transición, para mirarlo desde la dirección actual a una
+    // distancia en que se vea entero y fuera del radio en que deja de dibujarse
+    pub fn start(camera: &Camera, target: usize, body: &Cuerpo, position: DVec3, primary: DVec3, frame: ReferenceFrame, views: &Viewpoints) -> Self {
+        let distance = (body.scale * 8.0).max(body.scale + body.disappearance_buffer + 1.0) as f64;
+        let direction = (camera.eye - camera.center).try_normalize(1e-12).unwrap_or(DVec3::z());
+        let arrival = Viewpoint { eye: position + direction * distance, center: position, up: camera.up, fov: camera.fov };
+
+        Follow {
+            target,
+            frame,
+            last_radial: position - primary,
+            approach: Some(views.transition(Viewpoint::from_camera(camera), arrival)),
+        }
+    }
+
+    // `primary` es la posición del cuerpo alrededor del cual orbita el seguido
+    pub fn update(&mut self, camera: &mut Camera, position: DVec3, primary: DVec3) {
+        let radial = position - primary;
+        // El cuerpo central no tiene dirección radial; ahí el marco corrotante es el inercial. El giro
+        // se calcula en f64, porque en un cuadro es diminuto comparado con el radio de la órbita.
+        let rotation = match self.frame {
+            ReferenceFrame::CoRotating => UnitQuaternion::rotation_between(&self.last_radial, &radial),
+            ReferenceFrame::Inertial => None,
+        };
+        self.last_radial = radial;
+
+        // Durante la llegada se mueve el destino y no la cámara, que la transición lleva hasta él
+        if let Some(approach) = &mut self.approach {
+            let goal = approach.target();
+            let mut offset = goal.eye - goal.center;
+            let mut up = goal.up;
+            if let Some(rotation) = rotation {
+                offset = rotation * offset;
+                up = (rotation * up.cast()).cast();
+            }
+            approach.retarget(Viewpoint { eye: position + offset, center: position, up, ..goal });
+            if approach.update(camera) {
+                self.approach = None;
+            }
+            return;
+        }
+
+        let mut offset = camera.eye - camera.center;
+        if let Some(rotation) = rotation {
+            offset = rotation * offset;
+            camera.up = (rotation * camera.up.cast()).cast();
+        }
+        camera.center = position;
+        camera.eye = position + offset;
+    }
+}
@@ -1,31 +1,35 @@
 use nalgebra_glm::{Vec3, Vec2};
-use crate::color::Color;
 
 pub struct Fragment {
     pub position: Vec2,
-    pub color: Color,
     pub depth: f32,
     pub normal: Vec3,
-    pub intensity: f32,
     pub vertex_position: Vec3,
+    pub tex_coords: Vec2,
+    pub coverage: u8,       // Máscara de muestras MSAA cubiertas (bit i = muestra i)
+    pub depth_slope: Vec2,  // Derivadas de la profundidad en x e y para obtenerla en cada muestra
 }
 
 impl Fragment {
     pub fn new(
         position: Vec2,
-        color: Color,
         depth: f32,
         normal: Vec3,
-        intensity: f32,
         vertex_position: Vec3,
     ) -> Self {  
         Fragment {
             position,
-            color,
             depth,
             normal,
-            intensity,
             vertex_position,
+            tex_coords: Vec2::zeros(),
+            coverage: 1,
+            depth_slope: Vec2::zeros(),
         }
     }
+
+    // Fracción del pixel que cubre el fragmento; las capas translúcidas la usan como opacidad extra
+    pub fn coverage_fraction(&self, samples: usize) -> f32 {
+        self.coverage.count_ones() as f32 / samples as f32
+    }
 }
\ No newline at end of file
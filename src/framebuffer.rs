@@ -1,10 +1,63 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use nalgebra_glm::{Vec2, Vec3};
+use crate::color::Color;
+use crate::tonemap::ToneMapping;
+use crate::triangle::RasterStats;
+use crate::debugview::{display, heat_color};
+use crate::depth::DepthRange;
+use crate::font::{self, ADVANCE, GLYPH_WIDTH, LINE_HEIGHT};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Alpha,    // Interpola hacia el color según su opacidad
+    Additive, // Suma luz (atmósferas, resplandores, escape de motores)
+    Multiply, // Filtra lo que hay detrás (vidrios tintados, extinción)
+}
+
+// Estilo de las líneas: órbitas, estelas, alambre y guías de las etiquetas
+#[derive(Debug, Clone, Copy)]
+pub struct LineStyle {
+    pub color: Color,
+    pub alpha: f32,
+    pub dash: Option<(f32, f32)>, // Largo del trazo y del hueco en pixeles; `None` = línea continua
+}
+
+impl LineStyle {
+    pub fn solid(color: Color, alpha: f32) -> Self {
+        LineStyle { color, alpha, dash: None }
+    }
+
+    pub fn dashed(color: Color, alpha: f32, on: f32, off: f32) -> Self {
+        LineStyle { color, alpha, dash: Some((on, off)) }
+    }
+}
+
+// Fragmento translúcido pendiente de mezclar cuando termine la geometría opaca
+struct TranslucentFragment {
+    index: usize,
+    depth: f32,
+    color: Color,
+    alpha: f32,
+    mode: BlendMode,
+}
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
-    pub buffer: Vec<u32>,
+    pub buffer: Vec<u32>,  // Cuadro final en sRGB, listo para la ventana
+    pub color: Vec<Color>, // Acumulación HDR en espacio lineal
     pub zbuffer: Vec<f32>,
-    background_color: u32,
-    current_color: u32,
+    pub depth: DepthRange,         // Cómo está codificado el zbuffer
+    pub raster_stats: RasterStats, // Se reinician con cada `clear`
+    background_color: Color,
+    current_color: Color,
+    translucent: Vec<TranslucentFragment>,
+    sample_offsets: Vec<Vec2>,     // Patrón MSAA; con una sola muestra no se usan los buffers de abajo
+    sample_color: Vec<Color>,
+    sample_depth: Vec<f32>,
+    overdraw: Vec<u16>,            // Fragmentos por pixel, solo en la vista de overdraw
+    wireframe: Vec<[Vec3; 3]>,     // Triángulos en pantalla para la vista de alambre
 }
 
 impl Framebuffer {
@@ -13,47 +66,572 @@ impl Framebuffer {
             width,
             height,
             buffer: vec![0; width * height],
+            color: vec![Color::black(); width * height],
             zbuffer: vec![f32::INFINITY; width * height],
-            background_color: 0x000000,
-            current_color: 0xFFFFFF,
+            depth: DepthRange::default(),
+            raster_stats: RasterStats::default(),
+            background_color: Color::black(),
+            current_color: Color::from_float(1.0, 1.0, 1.0),
+            translucent: Vec::new(),
+            sample_offsets: vec![Vec2::new(0.5, 0.5)],
+            sample_color: Vec::new(),
+            sample_depth: Vec::new(),
+            overdraw: Vec::new(),
+            wireframe: Vec::new(),
         }
     }
 
+    pub fn samples(&self) -> usize {
+        self.sample_offsets.len()
+    }
+
+    pub fn sample_offsets(&self) -> &[Vec2] {
+        &self.sample_offsets
+    }
+
+    // Activa MSAA con el patrón dado (o lo desactiva con una sola muestra)
+    pub fn set_sample_pattern(&mut self, pattern: &[Vec2]) {
+        self.sample_offsets = pattern.to_vec();
+        let count = if pattern.len() > 1 { self.width * self.height * pattern.len() } else { 0 };
+        self.sample_color = vec![self.background_color; count];
+        self.sample_depth = vec![f32::INFINITY; count];
+    }
+
     pub fn clear(&mut self) {
-        for pixel in self.buffer.iter_mut() {
+        for pixel in self.color.iter_mut() {
             *pixel = self.background_color;
         }
         for depth in self.zbuffer.iter_mut() {
             *depth = f32::INFINITY;
         }
+        for pixel in self.sample_color.iter_mut() {
+            *pixel = self.background_color;
+        }
+        for depth in self.sample_depth.iter_mut() {
+            *depth = f32::INFINITY;
+        }
+        self.translucent.clear();
+        self.raster_stats = RasterStats::default();
+        self.overdraw.clear();
+        self.wireframe.clear();
     }
 
     pub fn point(&mut self, x: usize, y: usize, depth: f32) {
         if x < self.width && y < self.height {
             let index = y * self.width + x;
             if self.zbuffer[index] > depth {
-                self.buffer[index] = self.current_color;
+                self.color[index] = self.current_color;
                 self.zbuffer[index] = depth;
             }
         }
     }
 
+    // Escritura con cobertura MSAA: cada muestra cubierta hace su propia prueba de profundidad
+    // con la profundidad del plano del triángulo en su posición, pero todas reciben el mismo color
+    pub fn point_coverage(&mut self, x: usize, y: usize, depth: f32, depth_slope: Vec2, coverage: u8) {
+        let samples = self.samples();
+        if samples == 1 {
+            self.point(x, y, depth);
+            return;
+        }
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let base = (y * self.width + x) * samples;
+        for (i, offset) in self.sample_offsets.iter().enumerate() {
+            if coverage & (1 << i) == 0 {
+                continue;
+            }
+            let sample_depth = depth + depth_slope.dot(&(offset - Vec2::new(0.5, 0.5)));
+            if self.sample_depth[base + i] > sample_depth {
+                self.sample_color[base + i] = self.current_color;
+                self.sample_depth[base + i] = sample_depth;
+            }
+        }
+    }
+
+    // Promedia las muestras MSAA en el buffer por pixel. La profundidad que queda es la más cercana,
+    // así las transparencias y el postproceso siguen trabajando pixel a pixel
+    pub fn resolve_samples(&mut self) {
+        let samples = self.samples();
+        if samples == 1 {
+            return;
+        }
+        let weight = 1.0 / samples as f32;
+        for index in 0..self.width * self.height {
+            let range = index * samples..(index + 1) * samples;
+            let mut sum = Color::black();
+            for color in &self.sample_color[range.clone()] {
+                sum = sum + *color;
+            }
+            self.color[index] = sum * weight;
+            self.zbuffer[index] = self.sample_depth[range].iter().copied().fold(f32::INFINITY, f32::min);
+        }
+    }
+
+    // Reduce este buffer (de mayor resolución) al tamaño de `target` promediando el área que cubre
+    // cada pixel de destino; funciona también con factores no enteros como √2
+    pub fn downsample_into(&self, target: &mut Framebuffer) {
+        let columns = area_weights(self.width, target.width);
+        let rows = area_weights(self.height, target.height);
+
+        // Pasada horizontal y luego vertical
+        let mut temp = vec![Color::black(); target.width * self.height];
+        for y in 0..self.height {
+            for (x, weights) in columns.iter().enumerate() {
+                let mut sum = Color::black();
+                for &(source_x, weight) in weights {
+                    sum = sum + self.color[y * self.width + source_x] * weight;
+                }
+                temp[y * target.width + x] = sum;
+            }
+        }
+        for (y, weights) in rows.iter().enumerate() {
+            for x in 0..target.width {
+                let mut sum = Color::black();
+                let mut depth = f32::INFINITY;
+                for &(source_y, weight) in weights {
+                    sum = sum + temp[source_y * target.width + x] * weight;
+                    for &(source_x, _) in &columns[x] {
+                        depth = depth.min(self.zbuffer[source_y * self.width + source_x]);
+                    }
+                }
+                target.color[y * target.width + x] = sum;
+                target.zbuffer[y * target.width + x] = depth;
+            }
+        }
+        target.depth = self.depth;
+    }
+
+    // Agranda este buffer (de menor resolución) al tamaño de `target`: el color con interpolación
+    // bilineal y la profundidad con el pixel más cercano, para que las etiquetas sigan ocultándose bien
+    pub fn upscale_into(&self, target: &mut Framebuffer) {
+        let scale_x = self.width as f32 / target.width as f32;
+        let scale_y = self.height as f32 / target.height as f32;
+        for y in 0..target.height {
+            let source_y = ((y as f32 + 0.5) * scale_y - 0.5).clamp(0.0, (self.height - 1) as f32);
+            let (y0, ty) = (source_y.floor() as usize, source_y.fract());
+            let y1 = (y0 + 1).min(self.height - 1);
+            for x in 0..target.width {
+                let source_x = ((x as f32 + 0.5) * scale_x - 0.5).clamp(0.0, (self.width - 1) as f32);
+                let (x0, tx) = (source_x.floor() as usize, source_x.fract());
+                let x1 = (x0 + 1).min(self.width - 1);
+
+                let top = self.color[y0 * self.width + x0].lerp(&self.color[y0 * self.width + x1], tx);
+                let bottom = self.color[y1 * self.width + x0].lerp(&self.color[y1 * self.width + x1], tx);
+                let nearest = source_y.round() as usize * self.width + source_x.round() as usize;
+                target.color[y * target.width + x] = top.lerp(&bottom, ty);
+                target.zbuffer[y * target.width + x] = self.zbuffer[nearest];
+            }
+        }
+        target.depth = self.depth;
+    }
+
+    // Mezcla inmediata con lo ya dibujado; pasa la prueba de profundidad pero no la escribe
+    pub fn blend(&mut self, x: usize, y: usize, depth: f32, color: Color, alpha: f32, mode: BlendMode) {
+        if x < self.width && y < self.height {
+            let index = y * self.width + x;
+            if self.zbuffer[index] > depth {
+                self.blend_index(index, color, alpha, mode);
+            }
+        }
+    }
+
+    // Guarda un fragmento translúcido para `resolve_translucent`, así el orden de dibujo no importa
+    pub fn push_translucent(&mut self, x: usize, y: usize, depth: f32, color: Color, alpha: f32, mode: BlendMode) {
+        if x < self.width && y < self.height && alpha > 0.0 {
+            self.translucent.push(TranslucentFragment {
+                index: y * self.width + x,
+                depth,
+                color,
+                alpha,
+                mode,
+            });
+        }
+    }
+
+    // Pase de transparencias: ordena de atrás hacia adelante y mezcla contra la profundidad opaca.
+    // El orden es estable, así que fragmentos con la misma profundidad se mezclan en el orden en que llegaron.
+    pub fn resolve_translucent(&mut self) {
+        let mut fragments = std::mem::take(&mut self.translucent);
+        fragments.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+
+        for fragment in &fragments {
+            if self.zbuffer[fragment.index] > fragment.depth {
+                self.blend_index(fragment.index, fragment.color, fragment.alpha, fragment.mode);
+            }
+        }
+
+        fragments.clear();
+        self.translucent = fragments;
+    }
+
+    fn blend_index(&mut self, index: usize, color: Color, alpha: f32, mode: BlendMode) {
+        let destination = self.color[index];
+        self.color[index] = match mode {
+            BlendMode::Alpha => destination.lerp(&color, alpha),
+            BlendMode::Additive => destination + color * alpha,
+            BlendMode::Multiply => destination.lerp(&(destination * color), alpha),
+        };
+    }
+
+    // Lleva el buffer HDR al rango [0, 1]; a partir de aquí trabajan los efectos de postproceso
+    pub fn tone_map(&mut self, tone_mapping: &ToneMapping) {
+        for color in self.color.iter_mut() {
+            *color = tone_mapping.apply(*color);
+        }
+    }
+
+    // Codifica el cuadro en sRGB para la ventana; es el único punto donde se aplica gamma
+    pub fn present(&mut self) {
+        for (pixel, color) in self.buffer.iter_mut().zip(self.color.iter()) {
+            *pixel = color.to_hex();
+        }
+    }
+
     pub fn set_background_color(&mut self, color: u32) {
-        self.background_color = color;
+        self.background_color = Color::from_hex(color);
     }
 
-    pub fn set_current_color(&mut self, color: u32) {
+    pub fn set_current_color(&mut self, color: Color) {
         self.current_color = color;
     }
 
-    pub fn draw_stars(&mut self, stars: &[(usize, usize)]) {
-        for &(x, y) in stars {
-            if x < self.width && y < self.height {
-                let index = y * self.width + x;
-                self.buffer[index] = 0xFFFFFF; // Color blanco para las estrellas
+    // Línea antialiasada (algoritmo de Wu) con prueba de profundidad contra lo ya dibujado; no escribe
+    // el zbuffer. `from` y `to` son posiciones de pantalla con la profundidad en z, como las de los vértices.
+    pub fn line(&mut self, from: Vec3, to: Vec3, style: &LineStyle) {
+        self.line_from_phase(from, to, style, 0.0);
+    }
+
+    // Segmentos encadenados; el patrón de guiones continúa de un segmento al siguiente
+    pub fn polyline(&mut self, points: &[Vec3], style: &LineStyle) {
+        let mut phase = 0.0;
+        for pair in points.windows(2) {
+            phase += self.line_from_phase(pair[0], pair[1], style, phase);
+        }
+    }
+
+    // Como `line`, pero el patrón de guiones empieza desplazado `phase` pixeles. Devuelve el largo del
+    // segmento completo para que quien encadena segmentos pueda continuar el patrón.
+    pub fn line_from_phase(&mut self, from: Vec3, to: Vec3, style: &LineStyle, phase: f32) -> f32 {
+        let (width, height) = (self.width, self.height);
+        wu_line(from, to, width, height, style.dash, phase, |x, y, depth, weight| {
+            self.blend(x, y, depth, style.color, style.alpha * weight, BlendMode::Alpha);
+        })
+    }
+
+    // Cuenta un fragmento para la vista de overdraw, pase o no la prueba de profundidad
+    pub fn count_overdraw(&mut self, x: usize, y: usize) {
+        if self.overdraw.is_empty() {
+            self.overdraw = vec![0; self.width * self.height];
+        }
+        if x < self.width && y < self.height {
+            let count = &mut self.overdraw[y * self.width + x];
+            *count = count.saturating_add(1);
+        }
+    }
+
+    // Reemplaza el cuadro por la escala de calor del overdraw
+    pub fn show_overdraw(&mut self) {
+        for (index, color) in self.color.iter_mut().enumerate() {
+            *color = heat_color(self.overdraw.get(index).copied().unwrap_or(0));
+        }
+    }
+
+    // Reemplaza el cuadro por la distancia lineal a la cámara: blanco lo más cercano, negro lo más lejano.
+    // La escala es logarítmica para que la nave a un paso de la cámara no aplaste al resto de la escena.
+    pub fn show_depth(&mut self) {
+        let depth_range = self.depth;
+        let linear = |z: f32| depth_range.distance(z);
+        let (mut closest, mut farthest) = (f32::INFINITY, 0.0f32);
+        for &depth in self.zbuffer.iter().filter(|depth| depth.is_finite()) {
+            closest = closest.min(linear(depth));
+            farthest = farthest.max(linear(depth));
+        }
+        let range = (farthest / closest).ln().max(1e-6);
+        for (color, &depth) in self.color.iter_mut().zip(self.zbuffer.iter()) {
+            *color = if depth.is_finite() {
+                let gray = 1.0 - 0.9 * (linear(depth) / closest).ln() / range;
+                display(gray, gray, gray)
+            } else {
+                Color::black()
+            };
+        }
+    }
+
+    // Guarda un triángulo (posiciones de pantalla con profundidad) para `draw_wireframe`
+    pub fn record_wireframe(&mut self, triangle: [Vec3; 3]) {
+        self.wireframe.push(triangle);
+    }
+
+    // Aristas de los triángulos frontales guardados. Se adelantan un 1 % de su distancia para que
+    // pasen la prueba de profundidad contra su propia superficie.
+    pub fn draw_wireframe(&mut self, style: &LineStyle) {
+        let triangles = std::mem::take(&mut self.wireframe);
+        for [a, b, c] in &triangles {
+            let area = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+            if area >= 0.0 {
+                continue;
+            }
+            let bias = |v: &Vec3| Vec3::new(v.x, v.y, self.depth.nudge(v.z, 0.01));
+            let (a, b, c) = (bias(a), bias(b), bias(c));
+            self.polyline(&[a, b, c, a], style);
+        }
+        self.wireframe = triangles;
+    }
+
+    // Texto con la fuente de mapa de bits, directo sobre el cuadro final (después de `present`) para que
+    // ni la exposición ni el postproceso lo alteren. `scale` agranda cada pixel del glifo y `\n` baja de línea.
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, color: u32, scale: usize, alpha: f32) {
+        let scale = scale.max(1) as i32;
+        for (line_index, line) in text.lines().enumerate() {
+            let top = y + (line_index * LINE_HEIGHT) as i32 * scale;
+            for (char_index, c) in line.chars().enumerate() {
+                let left = x + (char_index * ADVANCE) as i32 * scale;
+                let rows = font::glyph(c);
+                for (row, bits) in rows.iter().enumerate() {
+                    for column in 0..GLYPH_WIDTH {
+                        if bits & (0x10 >> column) != 0 {
+                            self.fill_rect(left + column as i32 * scale, top + row as i32 * scale, scale as usize, scale as usize, color, alpha);
+                        }
+                    }
+                }
             }
         }
     }
 
-    
-}
\ No newline at end of file
+    // Rectángulo sobre el cuadro final, mezclado en sRGB; sirve de fondo para los paneles del HUD
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: usize, height: usize, color: u32, alpha: f32) {
+        let x0 = x.clamp(0, self.width as i32) as usize;
+        let y0 = y.clamp(0, self.height as i32) as usize;
+        let x1 = (x + width as i32).clamp(0, self.width as i32) as usize;
+        let y1 = (y + height as i32).clamp(0, self.height as i32) as usize;
+        let alpha = alpha.clamp(0.0, 1.0);
+        for row in y0..y1 {
+            for pixel in &mut self.buffer[row * self.width + x0..row * self.width + x1] {
+                *pixel = mix_hex(*pixel, color, alpha);
+            }
+        }
+    }
+
+    // Suma luz a un pixel (y a todas sus muestras MSAA); se usa para el fondo del cielo
+    pub fn add_light(&mut self, x: usize, y: usize, light: Color) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index = y * self.width + x;
+        self.color[index] = self.color[index] + light;
+        let samples = self.samples();
+        if samples > 1 {
+            for color in &mut self.sample_color[index * samples..(index + 1) * samples] {
+                *color = *color + light;
+            }
+        }
+    }
+
+    // Reparte una fuente puntual entre los cuatro pixeles más cercanos (coordenadas de centros de pixel)
+    pub fn splat(&mut self, x: f32, y: f32, light: Color) {
+        if x <= -1.0 || y <= -1.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return;
+        }
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        for (dx, dy, weight) in [
+            (0, 0, (1.0 - tx) * (1.0 - ty)),
+            (1, 0, tx * (1.0 - ty)),
+            (0, 1, (1.0 - tx) * ty),
+            (1, 1, tx * ty),
+        ] {
+            let (px, py) = (x0 as i64 + dx, y0 as i64 + dy);
+            if px >= 0 && py >= 0 {
+                self.add_light(px as usize, py as usize, light * weight);
+            }
+        }
+    }
+
+    // Guarda el cuadro presentado como PPM binario (P6)
+    pub fn save_ppm(&self, filename: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(filename)?);
+        write!(file, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in &self.buffer {
+            file.write_all(&[(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8])?;
+        }
+        file.flush()
+    }
+}
+
+// Algoritmo de Wu en coordenadas de pantalla: avanza un pixel a la vez sobre el eje mayor y reparte la
+// cobertura entre los dos pixeles del eje menor. `plot` recibe pixeles dentro de la pantalla, la profundidad
+// interpolada y el peso. Devuelve el largo en pixeles del segmento completo, para continuar los guiones.
+fn wu_line<F: FnMut(usize, usize, f32, f32)>(
+    from: Vec3,
+    to: Vec3,
+    width: usize,
+    height: usize,
+    dash: Option<(f32, f32)>,
+    phase: f32,
+    mut plot: F,
+) -> f32 {
+    let length = (to.xy() - from.xy()).magnitude();
+
+    // Coordenadas relativas a los centros de pixel, recortadas a la pantalla
+    let center = Vec3::new(0.5, 0.5, 0.0);
+    let (a, b) = (from - center, to - center);
+    let Some((t0, t1)) = clip_segment(a, b, width as f32, height as f32) else {
+        return length;
+    };
+
+    let steep = (b.y - a.y).abs() > (b.x - a.x).abs();
+    let major = |p: &Vec3| if steep { p.y } else { p.x };
+    let minor = |p: &Vec3| if steep { p.x } else { p.y };
+    let (t_start, t_end) = if major(&a) <= major(&b) { (t0, t1) } else { (t1, t0) };
+    let (start, end) = (a + (b - a) * t_start, a + (b - a) * t_end);
+    let span = major(&end) - major(&start);
+
+    for m in major(&start).round() as i64..=major(&end).round() as i64 {
+        let s = if span > 0.0 { ((m as f32 - major(&start)) / span).clamp(0.0, 1.0) } else { 0.0 };
+        let t = t_start + (t_end - t_start) * s;
+        if let Some((on, off)) = dash {
+            if (phase + t * length).rem_euclid(on + off) >= on {
+                continue;
+            }
+        }
+
+        let point = start + (end - start) * s;
+        let base = minor(&point).floor();
+        let fraction = minor(&point) - base;
+        for (offset, weight) in [(0, 1.0 - fraction), (1, fraction)] {
+            let n = base as i64 + offset;
+            let (x, y) = if steep { (n, m) } else { (m, n) };
+            if x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && weight > 0.0 {
+                plot(x as usize, y as usize, point.z, weight);
+            }
+        }
+    }
+
+    length
+}
+
+fn mix_hex(from: u32, to: u32, alpha: f32) -> u32 {
+    if alpha >= 1.0 {
+        return to;
+    }
+    let channel = |shift: u32| {
+        let a = ((from >> shift) & 0xFF) as f32;
+        let b = ((to >> shift) & 0xFF) as f32;
+        ((a + (b - a) * alpha).round() as u32) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
+
+// Recorte de Liang-Barsky del segmento `a`-`b` al rectángulo de la pantalla (con un pixel de margen
+// para el antialiasing). Devuelve el tramo visible como parámetros entre 0 y 1.
+fn clip_segment(a: Vec3, b: Vec3, width: f32, height: f32) -> Option<(f32, f32)> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for (p, q) in [(-dx, a.x + 1.0), (dx, width - a.x), (-dy, a.y + 1.0), (dy, height - a.y)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let r = q / p;
+            if p < 0.0 {
+                t0 = t0.max(r);
+            } else {
+                t1 = t1.min(r);
+            }
+        }
+    }
+    (t0 <= t1).then_some((t0, t1))
+}
+
+// Para cada pixel de destino, los pixeles de origen que cubre y la fracción de área de cada uno
+fn area_weights(source: usize, target: usize) -> Vec<Vec<(usize, f32)>> {
+    let scale = source as f32 / target as f32;
+    (0..target)
+        .map(|i| {
+            let start = i as f32 * scale;
+            let end = (start + scale).min(source as f32);
+            let mut weights = Vec::new();
+            let mut s = start.floor() as usize;
+            while (s as f32) < end && s < source {
+                let overlap = end.min(s as f32 + 1.0) - start.max(s as f32);
+                if overlap > 0.0 {
+                    weights.push((s, overlap / scale));
+                }
+                s += 1;
+            }
+            weights
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKGROUND: Color = Color { r: 0.2, g: 0.4, b: 0.6 };
+    const PAINT: Color = Color { r: 1.0, g: 0.0, b: 0.5 };
+
+    // Cuadro de una fila con el mismo color de fondo en todos los pixeles
+    fn framebuffer(width: usize) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(width, 1);
+        framebuffer.color.fill(BACKGROUND);
+        framebuffer
+    }
+
+    fn assert_color(color: Color, expected: (f32, f32, f32), what: &str) {
+        let close = (color.r - expected.0).abs() < 1e-6 && (color.g - expected.1).abs() < 1e-6 && (color.b - expected.2).abs() < 1e-6;
+        assert!(close, "{what}: {color:?} en vez de {expected:?}");
+    }
+
+    #[test]
+    fn blend_modes() {
+        let mut framebuffer = framebuffer(3);
+        framebuffer.blend(0, 0, 0.5, PAINT, 0.25, BlendMode::Alpha);
+        framebuffer.blend(1, 0, 0.5, PAINT, 0.25, BlendMode::Additive);
+        framebuffer.blend(2, 0, 0.5, PAINT, 0.25, BlendMode::Multiply);
+        // destino + (color - destino) * a
+        assert_color(framebuffer.color[0], (0.4, 0.3, 0.575), "alpha");
+        // destino + color * a
+        assert_color(framebuffer.color[1], (0.45, 0.4, 0.725), "aditiva");
+        // destino * (1 - a + color * a)
+        assert_color(framebuffer.color[2], (0.2, 0.3, 0.525), "multiplicativa");
+    }
+
+    #[test]
+    fn blend_respects_opaque_depth() {
+        let mut framebuffer = framebuffer(2);
+        framebuffer.zbuffer.fill(0.5);
+        framebuffer.blend(0, 0, 0.7, PAINT, 1.0, BlendMode::Alpha);
+        framebuffer.blend(1, 0, 0.3, PAINT, 1.0, BlendMode::Alpha);
+        assert_color(framebuffer.color[0], (0.2, 0.4, 0.6), "detrás de lo opaco");
+        assert_color(framebuffer.color[1], (1.0, 0.0, 0.5), "delante de lo opaco");
+    }
+
+    #[test]
+    fn translucent_fragments_resolve_back_to_front() {
+        let red = Color { r: 1.0, g: 0.0, b: 0.0 };
+        let blue = Color { r: 0.0, g: 0.0, b: 1.0 };
+        let mut framebuffer = framebuffer(3);
+        framebuffer.color.fill(Color::black());
+        framebuffer.zbuffer[2] = 0.5;
+
+        // El más cercano llega primero, pero tiene que mezclarse último
+        framebuffer.push_translucent(0, 0, 0.2, red, 0.5, BlendMode::Alpha);
+        framebuffer.push_translucent(0, 0, 0.8, blue, 0.5, BlendMode::Alpha);
+        // Con la misma profundidad se respeta el orden de llegada
+        framebuffer.push_translucent(1, 0, 0.4, blue, 0.5, BlendMode::Alpha);
+        framebuffer.push_translucent(1, 0, 0.4, red, 0.5, BlendMode::Alpha);
+        // Tapado por la geometría opaca
+        framebuffer.push_translucent(2, 0, 0.8, red, 1.0, BlendMode::Alpha);
+
+        assert_color(framebuffer.color[0], (0.0, 0.0, 0.0), "antes de resolver");
+        framebuffer.resolve_translucent();
+        assert_color(framebuffer.color[0], (0.5, 0.0, 0.25), "de atrás hacia adelante");
+        assert_color(framebuffer.color[1], (0.5, 0.0, 0.25), "orden de llegada");
+        assert_color(framebuffer.color[2], (0.0, 0.0, 0.0), "tapado");
+        assert!(framebuffer.translucent.is_empty());
+    }
+}
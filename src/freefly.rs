@@ -0,0 +1,105 @@
+use nalgebra::UnitQuaternion;
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::settings::Settings;
+
+// Parámetros del vuelo libre, sección `[flight]`
+#[derive(Debug, Clone, Copy)]
+pub struct Flight {
+    pub acceleration: f32,      // Unidades por segundo al cuadrado
+    pub max_speed: f32,         // Unidades por segundo
+    pub damping: f32,           // Fracción de la velocidad que se pierde por segundo, en escala exponencial
+    pub turn_rate: f32,         // Radianes por segundo con las teclas
+    pub mouse_sensitivity: f32, // Radianes por pixel
+    pub boost: f32,             // Multiplicador de aceleración y velocidad máxima con Shift
+}
+
+impl Flight {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Flight {
+            acceleration: settings.get_f32("flight.acceleration", 4.0),
+            max_speed: settings.get_f32("flight.max_speed", 3.0),
+            damping: settings.get_f32("flight.damping", 2.5).max(0.0),
+            turn_rate: settings.get_f32("flight.turn_rate", 1.2),
+            mouse_sensitivity: settings.get_f32("flight.mouse_sensitivity", 0.004),
+            boost: settings.get_f32("flight.boost", 4.0).max(1.0),
+        }
+    }
+}
+
+// Lo que pidió el usuario en este cuadro. Los ejes son los de la cámara: x a la derecha, y hacia
+// arriba y z hacia adelante; cada componente va de -1 a 1.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlightInput {
+    pub thrust: Vec3,
+    pub turn: Vec3,        // Cabeceo hacia arriba, guiñada y alabeo hacia la derecha
+    pub mouse: (f32, f32), // Desplazamiento del ratón en pixeles, y hacia abajo
+    pub boost: bool,
+}
+
+// Cámara libre de seis grados de libertad. La orientación es un cuaternión, así que no hay límites de
+// cabeceo ni bloqueo de ejes; el eje z local apunta hacia adelante y el y hacia arriba, como en
+// `UnitQuaternion::face_towards`.
+pub struct FreeFly {
+    flight: Flight,
+    orientation: UnitQuaternion<f32>,
+    velocity: Vec3,         // En el mundo
+    angular_velocity: Vec3, // Local: cabeceo, guiñada y alabeo en radianes por segundo
+    focus: f32,             // Distancia al centro de la cámara, que se conserva al volver a la órbita
+}
+
+impl FreeFly {
+    // Arranca con la vista actual, de modo que cambiar de modo no mueve la imagen
+    pub fn from_camera(camera: &Camera, flight: Flight) -> Self {
+        let offset = camera.relative(&camera.center);
+        let forward = offset.try_normalize(1e-6).unwrap_or(-Vec3::z());
+        let up = if forward.cross(&camera.up).magnitude() > 1e-4 { camera.up } else { Vec3::x() };
+        FreeFly {
+            flight,
+            orientation: UnitQuaternion::face_towards(&forward, &up),
+            velocity: Vec3::zeros(),
+            angular_velocity: Vec3::zeros(),
+            focus: offset.magnitude().max(1.0),
+        }
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.velocity.magnitude()
+    }
+
+    pub fn update(&mut self, camera: &mut Camera, input: &FlightInput, dt: f32) {
+        let flight = self.flight;
+        let boost = if input.boost { flight.boost } else { 1.0 };
+        // El eje x de `face_towards` apunta a la izquierda, de ahí el signo
+        let right = self.orientation * -Vec3::x();
+        let up = self.orientation * Vec3::y();
+        let forward = self.orientation * Vec3::z();
+
+        // Empuje en los ejes de la cámara y amortiguación exponencial, independiente de los FPS
+        let thrust = right * input.thrust.x + up * input.thrust.y + forward * input.thrust.z;
+        self.velocity += thrust * flight.acceleration * boost * dt;
+        self.velocity *= (-flight.damping * dt).exp();
+        let max_speed = flight.max_speed * boost;
+        if self.velocity.magnitude() > max_speed {
+            self.velocity = self.velocity.normalize() * max_speed;
+        }
+
+        // Las teclas aceleran el giro hasta `turn_rate` y lo frenan al soltarlas
+        let target = input.turn * flight.turn_rate;
+        let blend = 1.0 - (-8.0 * dt).exp();
+        self.angular_velocity += (target - self.angular_velocity) * blend;
+
+        // El ratón gira directamente, sin inercia, para que la mira siga a la mano
+        let pitch = self.angular_velocity.x * dt - input.mouse.1 * flight.mouse_sensitivity;
+        let yaw = self.angular_velocity.y * dt + input.mouse.0 * flight.mouse_sensitivity;
+        let roll = self.angular_velocity.z * dt;
+        // Rotaciones alrededor de los ejes locales (x a la izquierda, y arriba, z adelante)
+        let rotation = UnitQuaternion::from_scaled_axis(Vec3::new(-pitch, -yaw, roll));
+        self.orientation = UnitQuaternion::new_normalize(*(self.orientation * rotation).quaternion());
+
+        let forward = self.orientation * Vec3::z();
+        camera.eye += (self.velocity * dt).cast();
+        camera.center = camera.eye + (forward * self.focus).cast();
+        camera.up = self.orientation * Vec3::y();
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use nalgebra_glm::DVec3;
+use crate::calendar::CalendarDate;
+use crate::depth::DepthRange;
+use crate::ephemeris::J2000;
+use crate::font;
+use crate::framebuffer::Framebuffer;
+use crate::settings::Settings;
+
+// Cuadros que se promedian para el FPS; con pocos el número salta demasiado para leerlo
+const FPS_WINDOW: usize = 30;
+const MARGIN: i32 = 8;
+const PADDING: i32 = 6;
+const TEXT_COLOR: u32 = 0xE8ECF0;
+const DIM_COLOR: u32 = 0x8C96A0;
+const PANEL_COLOR: u32 = 0x000000;
+const ERROR_COLOR: u32 = 0xFF8070;
+// Tiempo que queda a la vista el aviso de un cambio hecho con el teclado
+const MESSAGE_TIME: Duration = Duration::from_secs(3);
+
+const HELP: &str = "\
+Controles
+Clic              seleccionar un cuerpo y centrar la cámara
+C                 vuelo libre / órbita
+Flechas izq/der   orbitar la cámara
+W / S             orbitar arriba / abajo
+A / D / Q / E     mover el centro de la cámara
+Flechas arr/ab    acercar / alejar
+B / Inicio        vista superior / inicial
+V                 volver a la vista anterior
+K                 apilar la vista actual
+Alt + 1-9         ir a una vista con nombre
+Alt+Shift + 1-9   guardar la vista con nombre
+, / .             simulación más lenta / rápida
+Espacio           pausar la simulación
+Intro             ir a una fecha
+T / + / -         mapeo de tonos y exposición
+G                 bloom
+X                 antialiasing
+1-9               efectos de postproceso
+O / P             órbitas / estelas
+J / U             reproducir / ver el recorrido de cámara
+Ctrl + 1-9        órbita de cada cuerpo
+F1-F6             vistas de depuración
+L                 etiquetas de los cuerpos
+Tab / Shift+Tab   seguir el cuerpo siguiente / anterior
+F                 seguir o soltar el cuerpo elegido
+R                 marco inercial o corrotante
+Rueda del ratón   campo de visión (zoom)
+Z                 modo del zbuffer
+N                 escala real / legible del sistema solar
+Vuelo libre:
+  W / S, A / D    avanzar / retroceder, a los lados
+  RePág / AvPág   subir / bajar
+  Flechas         cabeceo y guiñada
+  Q / E           alabeo
+  Shift           acelerar más
+  Clic derecho    mirar con el ratón
+I                 mostrar u ocultar el HUD
+H                 esta ayuda
+Esc               salir";
+
+// Cuerpo seleccionado con el ratón o, si no hay selección, al que apunta la cámara
+pub struct Target<'a> {
+    pub name: &'a str,
+    pub selected: bool,
+    pub distance: f32,
+    pub speed: f32,
+    pub radius: f32,
+    pub orbit_radius: f32,
+}
+
+// Estado de la simulación que se muestra en cada cuadro
+pub struct HudInfo<'a> {
+    pub days: f64, // Días desde J2000, en TDB
+    pub time_speed: u32,
+    pub paused: bool,
+    pub camera_position: DVec3,
+    pub fov: f32, // Vertical, en radianes
+    pub depth: DepthRange,
+    pub target: Option<Target<'a>>,
+    pub camera_mode: String,
+    pub render_size: (usize, usize), // Resolución a la que se dibuja la escena, antes de agrandarla
+}
+
+pub struct Hud {
+    pub visible: bool,
+    pub show_help: bool,
+    pub scale: usize,
+    frame_times: VecDeque<f32>, // Segundos entre cuadros
+    render_time: f32,           // Segundos que tomó dibujar el último cuadro
+    message: Option<(String, Instant)>, // Último aviso y cuándo se dio
+}
+
+impl Hud {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Hud {
+            visible: settings.get_bool("hud.visible", true),
+            show_help: settings.get_bool("hud.help", false),
+            scale: settings.get_usize("hud.scale", 1).max(1),
+            frame_times: VecDeque::with_capacity(FPS_WINDOW),
+            render_time: 0.0,
+            message: None,
+        }
+    }
+
+    // `frame_time` es el tiempo total desde el cuadro anterior y `render_time` solo el de `Renderer::draw`
+    pub fn record_frame(&mut self, frame_time: f32, render_time: f32) {
+        if self.frame_times.len() == FPS_WINDOW {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+        self.render_time = render_time;
+    }
+
+    pub fn fps(&self) -> f32 {
+        let total: f32 = self.frame_times.iter().sum();
+        if total > 0.0 {
+            self.frame_times.len() as f32 / total
+        } else {
+            0.0
+        }
+    }
+
+    // Se dibuja sobre el cuadro ya presentado
+    pub fn draw(&self, framebuffer: &mut Framebuffer, info: &HudInfo) {
+        if !self.visible {
+            return;
+        }
+
+        let average = if self.frame_times.is_empty() {
+            0.0
+        } else {
+            self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+        };
+        let mut status = format!(
+            "FPS {:.1} ({:.1} ms, render {:.1} ms)\n",
+            self.fps(),
+            average * 1000.0,
+            self.render_time * 1000.0
+        );
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        status += &format!("Ventana {}x{}", width, height);
+        if info.render_size != (width, height) {
+            status += &format!(", escena {}x{}", info.render_size.0, info.render_size.1);
+        }
+        status += "\n";
+        let speed = if info.paused { "en pausa".to_string() } else { format!("x{}", info.time_speed) };
+        let julian_day = J2000 + info.days;
+        status += &format!("Fecha {} TDB, {}\n", CalendarDate::from_julian_day(julian_day), speed);
+        status += &format!("  JD {:.5}\n", julian_day);
+        let eye = info.camera_position;
+        status += &format!("Cámara ({:.2}, {:.2}, {:.2})\n", eye.x, eye.y, eye.z);
+        status += &format!(
+            "  fov {:.1}°, planos {} a {}, profundidad {}\n",
+            info.fov.to_degrees(),
+            info.depth.near,
+            info.depth.far,
+            info.depth.mode.name()
+        );
+        match &info.target {
+            Some(target) => {
+                let title = if target.selected { "Seleccionado" } else { "Objetivo" };
+                status += &format!("{} {}\n", title, target.name);
+                status += &format!("  distancia {:.2}\n", target.distance);
+                status += &format!("  velocidad {:.3} por paso", target.speed);
+                if target.selected {
+                    status += &format!("\n  radio {:.2}\n", target.radius);
+                    status += &format!("  radio orbital {:.2}", target.orbit_radius);
+                }
+            }
+            None => status += "Objetivo ninguno",
+        }
+        status += "\n";
+        status += &info.camera_mode;
+        self.panel(framebuffer, MARGIN, MARGIN, &status, TEXT_COLOR);
+
+        if self.show_help {
+            let width = self.text_size(HELP).0;
+            let x = (framebuffer.width as i32 - width) / 2;
+            self.panel(framebuffer, x.max(MARGIN), MARGIN, HELP, TEXT_COLOR);
+        } else {
+            let hint = "H: ayuda";
+            let y = framebuffer.height as i32 - MARGIN - self.text_size(hint).1 - 2 * PADDING;
+            self.panel(framebuffer, MARGIN, y, hint, DIM_COLOR);
+        }
+    }
+
+    // Avisa en pantalla de un cambio de estado, como activar un efecto; reemplaza al aviso anterior
+    pub fn notify(&mut self, message: String) {
+        self.message = Some((message, Instant::now()));
+    }
+
+    // Último aviso, abajo y al centro, durante unos segundos; se muestra aunque el HUD esté oculto
+    pub fn draw_message(&self, framebuffer: &mut Framebuffer) {
+        let Some((message, since)) = &self.message else {
+            return;
+        };
+        if since.elapsed() > MESSAGE_TIME {
+            return;
+        }
+        let (width, height) = self.text_size(message);
+        let x = (framebuffer.width as i32 - width) / 2 - PADDING;
+        let y = framebuffer.height as i32 - MARGIN - height - 2 * PADDING;
+        self.panel(framebuffer, x.max(MARGIN), y, message, TEXT_COLOR);
+    }
+
+    // Línea para escribir la fecha a la que saltar, abajo y al centro; se muestra aunque el HUD esté oculto
+    pub fn draw_date_prompt(&self, framebuffer: &mut Framebuffer, text: &str, invalid: bool) {
+        let hint = if invalid {
+            "No es una fecha válida"
+        } else {
+            "AAAA-MM-DD [HH:MM[:SS]] o JD; Intro salta, Esc cancela"
+        };
+        let prompt = format!("Ir a la fecha (TDB): {}_\n{}", text, hint);
+        let (width, height) = self.text_size(&prompt);
+        let x = (framebuffer.width as i32 - width) / 2 - PADDING;
+        let y = framebuffer.height as i32 - MARGIN - height - 2 * PADDING;
+        self.panel(framebuffer, x.max(MARGIN), y, &prompt, if invalid { ERROR_COLOR } else { TEXT_COLOR });
+    }
+
+    fn text_size(&self, text: &str) -> (i32, i32) {
+        let scale = self.scale as i32;
+        (font::text_width(text) as i32 * scale, font::text_height(text) as i32 * scale)
+    }
+
+    // Texto con sombra sobre un fondo semitransparente
+    fn panel(&self, framebuffer: &mut Framebuffer, x: i32, y: i32, text: &str, color: u32) {
+        let (width, height) = self.text_size(text);
+        framebuffer.fill_rect(x, y, (width + 2 * PADDING) as usize, (height + 2 * PADDING) as usize, PANEL_COLOR, 0.55);
+        let scale = self.scale as i32;
+        framebuffer.draw_text(x + PADDING + scale, y + PADDING + scale, text, PANEL_COLOR, self.scale, 1.0);
+        framebuffer.draw_text(x + PADDING, y + PADDING, text, color, self.scale, 1.0);
+    }
+}
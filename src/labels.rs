@@ -0,0 +1,220 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::color::Color;
+use crate::cuerpo::Cuerpo;
+use crate::font;
+use crate::framebuffer::{Framebuffer, LineStyle};
+use crate::settings::Settings;
+
+// Separación entre el borde del cuerpo en pantalla y el comienzo de la guía
+const GAP: f32 = 3.0;
+// Largo de la guía en cada eje
+const LEADER: f32 = 14.0;
+// Margen alrededor de cada etiqueta para decidir si dos se tocan
+const SPACING: i32 = 2;
+const HIGHLIGHT_COLOR: u32 = 0xFFE070;
+
+// Nombres de los cuerpos junto a su posición en pantalla, unidos por una guía
+pub struct Labels {
+    pub visible: bool,
+    pub fade_start: f32, // Distancia a partir de la cual la etiqueta empieza a desvanecerse
+    pub fade_end: f32,   // Distancia a la que ya no se ve
+}
+
+// Etiqueta ya proyectada, antes de elegir dónde va el texto
+struct Candidate<'a> {
+    name: &'a str,
+    color: Color,
+    alpha: f32,
+    x: f32,
+    y: f32,
+    depth: f32, // Profundidad guardada del frente del cuerpo, para la prueba de la guía
+    radius: f32, // Radio aparente del cuerpo en pixeles
+    distance: f32,
+}
+
+// Etiqueta con lugar asignado: la guía se mezcla antes de presentar el cuadro y el nombre después
+pub struct Label<'a> {
+    name: &'a str,
+    color: Color,
+    alpha: f32,
+    start: Vec3,
+    end: Vec3,
+    rect: Rect,
+}
+
+#[derive(Clone, Copy)]
+struct Rect {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Rect {
+    fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width + SPACING
+            && other.x < self.x + self.width + SPACING
+            && self.y < other.y + other.height + SPACING
+            && other.y < self.y + self.height + SPACING
+    }
+}
+
+impl Labels {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Labels {
+            visible: settings.get_bool("labels.visible", true),
+            fade_start: settings.get_f32("labels.fade_start", 30.0),
+            fade_end: settings.get_f32("labels.fade_end", 60.0),
+        }
+    }
+
+    // Elige dónde va cada etiqueta sin dibujar nada; el zbuffer es el de la escena
+    pub fn place<'a>(&self, framebuffer: &Framebuffer, bodies: &'a [Cuerpo], positions: &[Vec3], view_projection: &Mat4, eye: Vec3) -> Vec<Label<'a>> {
+        if !self.visible {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<Candidate> = bodies
+            .iter()
+            .zip(positions)
+            .filter_map(|(body, position)| self.project(framebuffer, body, *position, view_projection, eye))
+            .collect();
+
+        // Los más cercanos eligen lugar primero; si una etiqueta no cabe en ningún lado, se omite
+        candidates.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        let mut placed: Vec<Label> = Vec::new();
+        for candidate in &candidates {
+            let width = font::text_width(candidate.name) as i32;
+            let height = font::text_height(candidate.name) as i32;
+            let reach = candidate.radius * std::f32::consts::FRAC_1_SQRT_2 + GAP;
+
+            // Arriba a la derecha, arriba a la izquierda, abajo a la derecha y abajo a la izquierda;
+            // luego lo mismo con la guía el doble de larga
+            let placement = [1.0, 2.0]
+                .iter()
+                .flat_map(|length| [(1.0, -1.0), (-1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|side| (side, *length)))
+                .map(|((sx, sy), length): ((f32, f32), f32)| {
+                    let start = (candidate.x + sx * reach, candidate.y + sy * reach);
+                    let end = (start.0 + sx * LEADER * length, start.1 + sy * LEADER * length);
+                    let x = if sx > 0.0 { end.0 as i32 + 2 } else { end.0 as i32 - 2 - width };
+                    let rect = Rect { x, y: end.1 as i32 - height / 2, width, height };
+                    (start, end, rect)
+                })
+                .find(|(_, _, rect)| {
+                    rect.x >= 0
+                        && rect.y >= 0
+                        && rect.x + rect.width <= framebuffer.width as i32
+                        && rect.y + rect.height <= framebuffer.height as i32
+                        && !placed.iter().any(|other| other.rect.overlaps(rect))
+                });
+
+            let Some((start, end, rect)) = placement else {
+                continue;
+            };
+            placed.push(Label {
+                name: candidate.name,
+                color: candidate.color,
+                alpha: candidate.alpha,
+                start: Vec3::new(start.0, start.1, candidate.depth),
+                end: Vec3::new(end.0, end.1, candidate.depth),
+                rect,
+            });
+        }
+        placed
+    }
+
+    fn project<'a>(&self, framebuffer: &Framebuffer, body: &'a Cuerpo, position: Vec3, view_projection: &Mat4, eye: Vec3) -> Option<Candidate<'a>> {
+        let distance = (position - eye).magnitude();
+        let alpha = 1.0 - ((distance - self.fade_start) / (self.fade_end - self.fade_start).max(1e-6)).clamp(0.0, 1.0);
+        if alpha <= 0.0 || body.is_too_close(position, eye) {
+            return None;
+        }
+
+        let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+        let to_screen = |point: Vec3| project_point(view_projection, point, width, height);
+
+        // Detrás de la cámara o fuera de la pantalla
+        let center = to_screen(position)?;
+        if center.x < 0.0 || center.y < 0.0 || center.x >= width || center.y >= height {
+            return None;
+        }
+
+        // El punto de la superficie más cercano a la cámara: si el zbuffer tiene algo bastante más
+        // adelante, otro objeto tapa al cuerpo. La tolerancia es un 1 % de la distancia, como en el alambre.
+        let front = to_screen(position + (eye - position).normalize() * body.scale)?;
+        let depth = framebuffer.depth.stored(front.z * 0.99);
+        let (x, y) = (front.x as usize, front.y as usize);
+        if x < framebuffer.width && y < framebuffer.height {
+            let stored = framebuffer.zbuffer[y * framebuffer.width + x];
+            if stored < depth {
+                return None;
+            }
+        }
+
+        Some(Candidate {
+            name: &body.name,
+            color: body.color,
+            alpha,
+            x: center.x,
+            y: center.y,
+            depth,
+            radius: apparent_radius(view_projection, body, position, center, eye, width, height),
+            distance,
+        })
+    }
+}
+
+// Las guías se mezclan con el cuadro ya mapeado, antes de `present`, y se ocultan detrás de lo
+// que tape al cuerpo
+pub fn draw_leaders(framebuffer: &mut Framebuffer, labels: &[Label]) {
+    for label in labels {
+        framebuffer.line(label.start, label.end, &LineStyle::solid(label.color, label.alpha * 0.7));
+    }
+}
+
+// Los nombres van sobre el cuadro ya presentado para que el texto quede nítido
+pub fn draw_names(framebuffer: &mut Framebuffer, labels: &[Label]) {
+    for label in labels {
+        let Rect { x, y, .. } = label.rect;
+        framebuffer.draw_text(x + 1, y + 1, label.name, 0x000000, 1, label.alpha * 0.8);
+        framebuffer.draw_text(x, y, label.name, label.color.to_hex(), 1, label.alpha);
+    }
+}
+
+// Círculo alrededor del cuerpo seleccionado, a la profundidad de su frente como las guías; va antes de
+// `present`, igual que ellas
+pub fn draw_highlight(framebuffer: &mut Framebuffer, body: &Cuerpo, position: Vec3, view_projection: &Mat4, eye: Vec3) {
+    let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+    if body.is_too_close(position, eye) {
+        return;
+    }
+    let Some(center) = project_point(view_projection, position, width, height) else {
+        return;
+    };
+
+    let radius = apparent_radius(view_projection, body, position, center, eye, width, height) + 5.0;
+    let depth = framebuffer.depth.stored((center.z - body.scale) * 0.99);
+    let segments = 48;
+    let circle: Vec<Vec3> = (0..=segments)
+        .map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            Vec3::new(center.x + radius * angle.cos(), center.y + radius * angle.sin(), depth)
+        })
+        .collect();
+    framebuffer.polyline(&circle, &LineStyle::solid(Color::from_hex(HIGHLIGHT_COLOR), 0.9));
+}
+
+// Posición en pantalla con la distancia a la cámara (w) en z; `None` si queda detrás de la cámara
+fn project_point(view_projection: &Mat4, point: Vec3, width: f32, height: f32) -> Option<Vec3> {
+    let clip = view_projection * Vec4::new(point.x, point.y, point.z, 1.0);
+    (clip.w > 0.0).then(|| {
+        Vec3::new((clip.x / clip.w + 1.0) * 0.5 * width, (1.0 - clip.y / clip.w) * 0.5 * height, clip.w)
+    })
+}
+
+// Radio aparente en pixeles, a partir de un punto del borde perpendicular a la vista
+fn apparent_radius(view_projection: &Mat4, body: &Cuerpo, position: Vec3, center: Vec3, eye: Vec3, width: f32, height: f32) -> f32 {
+    let side = (eye - position).cross(&Vec3::y()).try_normalize(1e-6).unwrap_or(Vec3::x());
+    project_point(view_projection, position + side * body.scale, width, height)
+        .map_or(0.0, |edge| (edge.xy() - center.xy()).magnitude())
+}
@@ -1,7 +1,10 @@
-use nalgebra_glm::{look_at, perspective, Mat4, Vec3, Vec4};
-use minifb::{Key, Window, WindowOptions};
+use nalgebra_glm::{perspective, DVec3, Mat4, Vec2, Vec3, Vec4};
+use nalgebra::Point3;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
 use std::f32::consts::PI;
-use rand::Rng;
+use std::time::Instant;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 
 mod framebuffer;
 mod triangle;
@@ -12,27 +15,85 @@ mod fragment;
 mod shaders;
 mod camera;
 mod cuerpo;
-mod spaceship;
-
-use framebuffer::Framebuffer;
+mod material;
+mod atmosphere;
+mod ring;
+mod tonemap;
+mod bloom;
+mod settings;
+mod postprocess;
+mod antialiasing;
+mod starfield;
+mod overlay;
+mod debugview;
+mod font;
+mod hud;
+mod labels;
+mod picking;
+mod follow;
+mod viewpoint;
+mod freefly;
+mod cameramode;
+mod camerapath;
+mod depth;
+mod ephemeris;
+mod solarsystem;
+mod calendar;
+mod prompt;
+
+use framebuffer::{Framebuffer, BlendMode, LineStyle};
 use vertex::Vertex;
 use obj::Obj;
 use camera::Camera;
-use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader};
+use triangle::{triangle, RasterStats};
+use shaders::{vertex_shader, fragment_shader, ring_shader};
 use fastnoise_lite::{FastNoiseLite, NoiseType, FractalType};
-use cuerpo::Cuerpo;
-use spaceship::Spaceship;
-
-
-
-pub struct Uniforms {
+use cuerpo::{Cuerpo, Orbit, DISAPPEARANCE_BUFFER};
+use material::Material;
+use atmosphere::Atmosphere;
+use ring::{Ring, RingProfile};
+use tonemap::{ToneMapping, ToneMapOperator};
+use bloom::Bloom;
+use settings::Settings;
+use postprocess::{PostStack, PostEffect, Fxaa};
+use antialiasing::AntiAliasing;
+use starfield::Starfield;
+use overlay::Overlay;
+use debugview::DebugView;
+use hud::{Hud, HudInfo, Target};
+use labels::Labels;
+use follow::{Follow, ReferenceFrame};
+use viewpoint::{Transition, Viewpoint, Viewpoints, NAMED_SLOTS};
+use freefly::{Flight, FlightInput};
+use cameramode::CameraMode;
+use camerapath::{CameraPath, Playback};
+use depth::DepthRange;
+use solarsystem::SystemScale;
+use calendar::CalendarDate;
+use prompt::DatePrompt;
+use color::Color;
+use fragment::Fragment;
+
+
+
+const SHIP_SCALE: f32 = 0.03;
+const ORBITAL_SPEED: f64 = 0.01; // Velocidad de traslación de los planetas ficticios, en radianes por día
+// Las superficies se animan con los días módulo un siglo juliano: en f32 los días pierden la hora a partir
+// de un millón, y dentro del primer siglo desde J2000 la animación queda igual que sin reducir
+const SURFACE_PERIOD: f64 = 36525.0;
+
+pub struct Uniforms<'a> {
     model_matrix: Mat4,
     view_matrix: Mat4,
     projection_matrix: Mat4,
     viewport_matrix: Mat4,
-    time: u32,
-    noise: FastNoiseLite
+    days: f32, // Días desde J2000 módulo `SURFACE_PERIOD`, para animar las superficies
+    noise: FastNoiseLite,
+    material: Material,
+    sun_position: Vec3,
+    ring: Option<&'a Ring>,
+    debug_view: DebugView,
+    depth: DepthRange,
 }
 
 fn create_noise(_index: usize) -> FastNoiseLite {
@@ -118,97 +179,156 @@ fn create_plant3_noise() -> FastNoiseLite {
     noise
 }
 
-fn create_solar_system() -> Vec<Cuerpo> {
-    let mut rng = rand::thread_rng();
+fn create_solar_system(rng: &mut StdRng) -> Vec<Cuerpo> {
     vec![
         Cuerpo {
             name: "Sol".to_string(),
-            translation: Vec3::new(2.0, 0.0, -5.0),
-            rotation: Vec3::new(0.0, 0.0, 0.0),
             scale: 1.0f32,
             vertex_array: Obj::load("assets/models/cuerpo2.obj")
                 .expect("Failed to load sol model")
                 .get_vertex_array(),
-            orbit_radius: 0.0,
-            phase_offset: rng.gen_range(0.0..std::f32::consts::TAU),
-            rotation_speed: 0.0
+            orbit: Orbit::Circular { radius: 0.0, phase: rng.gen_range(0.0..std::f32::consts::TAU) as f64, speed: ORBITAL_SPEED },
+            parent: None,
+            pole: Vec3::y(),
+            rotation_speed: 0.0,
+            shader: 0,
+            material: Material::new(0.0, 4.0),
+            atmosphere: None,
+            ring: None,
+            color: Color::new(255, 210, 120),
+            disappearance_buffer: DISAPPEARANCE_BUFFER,
         },
         Cuerpo {
             name: "Volcanis".to_string(),
-            translation: Vec3::new(4.0, 0.0, -8.0),
-            rotation: Vec3::new(0.0, 0.0, 0.0),
             scale: 0.3f32,
             vertex_array: Obj::load("assets/models/cuerpo2.obj")
                 .expect("Failed to load Volcanis model")
                 .get_vertex_array(),
-            orbit_radius: 2.0,
-            phase_offset: rng.gen_range(0.0..std::f32::consts::TAU),
-            rotation_speed: rng.gen_range(0.01..0.05)
+            orbit: Orbit::Circular { radius: 2.0, phase: rng.gen_range(0.0..std::f32::consts::TAU) as f64, speed: ORBITAL_SPEED },
+            parent: None,
+            pole: Vec3::y(),
+            rotation_speed: rng.gen_range(0.01..0.05),
+            shader: 1,
+            material: Material::new(0.3, 2.5),
+            atmosphere: None,
+            ring: None,
+            color: Color::new(235, 110, 60),
+            disappearance_buffer: DISAPPEARANCE_BUFFER,
         },
         Cuerpo{
             name: "Morveth".to_string(),
-            translation: Vec3::new(4.0, 0.0, -8.0),
-            rotation: Vec3::new(0.0, 0.0, 0.0),
             scale: 0.4f32,
             vertex_array: Obj::load("assets/models/cuerpo2.obj")
                 .expect("Failed to load Morveth model")
                 .get_vertex_array(),
-            orbit_radius: 3.0,
-            phase_offset: rng.gen_range(0.0..std::f32::consts::TAU),
-            rotation_speed: rng.gen_range(0.01..0.05)
+            orbit: Orbit::Circular { radius: 3.0, phase: rng.gen_range(0.0..std::f32::consts::TAU) as f64, speed: ORBITAL_SPEED },
+            parent: None,
+            pole: Vec3::y(),
+            rotation_speed: rng.gen_range(0.01..0.05),
+            shader: 2,
+            material: Material::new(0.2, 0.0),
+            atmosphere: None,
+            ring: None,
+            color: Color::new(180, 140, 110),
+            disappearance_buffer: DISAPPEARANCE_BUFFER,
         },
         Cuerpo{
             name: "GaiaNova".to_string(),
-            translation: Vec3::new(4.0, 0.0, -8.0),
-            rotation: Vec3::new(0.0, 0.0, 0.0),
             scale:  0.4f32,
             vertex_array: Obj::load("assets/models/cuerpo2.obj")
                 .expect("Failed to load GaiaNova model")
                 .get_vertex_array(),
-            orbit_radius: 4.0,
-            phase_offset: rng.gen_range(0.0..std::f32::consts::TAU),
-            rotation_speed: rng.gen_range(0.01..0.05)
+            orbit: Orbit::Circular { radius: 4.0, phase: rng.gen_range(0.0..std::f32::consts::TAU) as f64, speed: ORBITAL_SPEED },
+            parent: None,
+            pole: Vec3::y(),
+            rotation_speed: rng.gen_range(0.01..0.05),
+            shader: 3,
+            material: Material::new(0.6, 0.0),
+            atmosphere: Some(Atmosphere::new(Vec3::new(0.18, 0.41, 1.0), 6.6, 0.15, 0.1, 0.76)),
+            ring: None,
+            color: Color::new(90, 170, 110),
+            disappearance_buffer: DISAPPEARANCE_BUFFER,
         },
         Cuerpo{
             name: "Aquarion".to_string(),
-            translation: Vec3::new(4.0, 0.0, -8.0),
-            rotation: Vec3::new(0.0, 0.0, 0.0),
             scale:  0.3f32,
             vertex_array: Obj::load("assets/models/cuerpo2.obj")
                 .expect("Failed to load Aquarion model")
                 .get_vertex_array(),
-            orbit_radius: 5.0,
-            phase_offset: rng.gen_range(0.0..std::f32::consts::TAU),
-            rotation_speed: rng.gen_range(0.01..0.05)
+            orbit: Orbit::Circular { radius: 5.0, phase: rng.gen_range(0.0..std::f32::consts::TAU) as f64, speed: ORBITAL_SPEED },
+            parent: None,
+            pole: Vec3::y(),
+            rotation_speed: rng.gen_range(0.01..0.05),
+            shader: 4,
+            material: Material::new(0.1, 0.0),
+            atmosphere: Some(Atmosphere::new(Vec3::new(0.1, 0.5, 0.9), 5.0, 0.12, 0.08, 0.7)),
+            ring: None,
+            color: Color::new(70, 140, 230),
+            disappearance_buffer: DISAPPEARANCE_BUFFER,
         },
         Cuerpo{
             name: "Stratos".to_string(),
-            translation: Vec3::new(4.0, 0.0, -8.0),
-            rotation: Vec3::new(0.0, 0.0, 0.0),
             scale: 0.8f32,
-            vertex_array: Obj::load("assets/models/saturno2.obj")
+            vertex_array: Obj::load("assets/models/cuerpo2.obj")
                 .expect("Failed to load Startos model")
                 .get_vertex_array(),
-            orbit_radius: 7.0,
-            phase_offset: rng.gen_range(0.0..std::f32::consts::TAU),
-            rotation_speed: rng.gen_range(0.01..0.05)
+            orbit: Orbit::Circular { radius: 7.0, phase: rng.gen_range(0.0..std::f32::consts::TAU) as f64, speed: ORBITAL_SPEED },
+            parent: None,
+            pole: Vec3::y(),
+            rotation_speed: rng.gen_range(0.01..0.05),
+            shader: 5,
+            material: Material::new(0.0, 0.0),
+            atmosphere: None,
+            ring: Some(Ring::new(1.25, 2.3, 0.45, 0.85, RingProfile::Procedural {
+                inner_color: Color::new(150, 130, 100),
+                outer_color: Color::new(215, 195, 160),
+            })),
+            color: Color::new(215, 195, 160),
+            disappearance_buffer: DISAPPEARANCE_BUFFER,
         },
         Cuerpo{
             name: "KratonV".to_string(),
-            translation: Vec3::new(4.0, 0.0, -8.0),
-            rotation: Vec3::new(0.0, 0.0, 0.0),
             scale: 0.09f32,
             vertex_array: Obj::load("assets/models/asteroide.obj")
                 .expect("Failed to load KratonV model")
                 .get_vertex_array(),
-            orbit_radius: 8.0,
-            phase_offset: rng.gen_range(0.0..std::f32::consts::TAU),
-            rotation_speed: rng.gen_range(0.01..0.05)
+            orbit: Orbit::Circular { radius: 8.0, phase: rng.gen_range(0.0..std::f32::consts::TAU) as f64, speed: ORBITAL_SPEED },
+            parent: None,
+            pole: Vec3::y(),
+            rotation_speed: rng.gen_range(0.01..0.05),
+            shader: 6,
+            material: Material::new(1.2, 0.0),
+            atmosphere: None,
+            ring: None,
+            color: Color::new(160, 160, 160),
+            disappearance_buffer: DISAPPEARANCE_BUFFER,
         },
     ]
 }
 
 
+// Cielo de fondo desde la sección [sky]: estrellas procedurales, un catálogo opcional y la Vía Láctea
+fn create_sky(rng: &mut StdRng, settings: &Settings) -> Starfield {
+    let mut stars = Starfield::procedural(
+        rng,
+        settings.get_usize("sky.stars", 3000),
+        settings.get_f32("sky.limiting_magnitude", 6.5),
+    );
+    if let Some(filename) = settings.get("sky.catalogue") {
+        match Starfield::load_catalogue(filename) {
+            Ok(catalogue) => stars.extend(catalogue),
+            Err(error) => eprintln!("No se pudo cargar el catálogo de estrellas: {}", error),
+        }
+    }
+
+    let sky = Starfield::new(stars, settings.get_f32("sky.brightness", 1.0));
+    if settings.get_bool("sky.milky_way", true) {
+        sky.with_milky_way(settings.get_f32("sky.milky_way_intensity", 0.025))
+    } else {
+        sky
+    }
+}
+
 fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
     let (sin_x, cos_x) = rotation.x.sin_cos();
     let (sin_y, cos_y) = rotation.y.sin_cos();
@@ -248,17 +368,10 @@ fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
 }
 
 
-fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
-    look_at(&eye, &center, &up)
-}
-
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 45.0 * PI / 180.0;
+// El campo de visión y los planos son de la cámara; nalgebra_glm recibe primero la relación de aspecto
+fn create_perspective_matrix(window_width: f32, window_height: f32, camera: &Camera) -> Mat4 {
     let aspect_ratio = window_width / window_height;
-    let near = 0.1;
-    let far = 1000.0;
-
-    perspective(fov, aspect_ratio, near, far)
+    perspective(aspect_ratio, camera.fov, camera.near, camera.far)
 }
 
 fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
@@ -270,7 +383,24 @@ fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
     )
 }
 
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], index: usize) {
+// Recorta el triángulo contra el plano cercano (w = near). La transformación hasta el espacio de recorte es
+// lineal, así que los vértices nuevos se interpolan en el espacio del modelo y pasan por el vertex shader.
+// Sin esto, un triángulo que cruza la cámara proyecta sus vértices detrás del ojo a cualquier parte.
+fn clip_near(tri: [&Vertex; 3], w: [f32; 3], near: f32) -> Vec<Vertex> {
+    let mut polygon = Vec::with_capacity(4);
+    for i in 0..3 {
+        let j = (i + 1) % 3;
+        if w[i] >= near {
+            polygon.push(tri[i].clone());
+        }
+        if (w[i] >= near) != (w[j] >= near) {
+            polygon.push(tri[i].lerp(tri[j], (near - w[i]) / (w[j] - w[i])));
+        }
+    }
+    polygon
+}
+
+fn rasterize(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex]) -> Vec<Fragment> {
     // Vertex Shader Stage
     let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
     for vertex in vertex_array {
@@ -278,170 +408,1219 @@ fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Ve
         transformed_vertices.push(transformed);
     }
 
-    // Primitive Assembly Stage
+    // Primitive Assembly Stage; los triángulos que cruzan el plano cercano se recortan
+    let w_row = (uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix).row(3).transpose();
+    let clip_w = |vertex: &Vertex| w_row.dot(&Vec4::new(vertex.position.x, vertex.position.y, vertex.position.z, 1.0));
+    let near = uniforms.depth.near;
     let mut triangles = Vec::new();
     for i in (0..transformed_vertices.len()).step_by(3) {
         if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+            let w = [clip_w(&vertex_array[i]), clip_w(&vertex_array[i + 1]), clip_w(&vertex_array[i + 2])];
+            if w.iter().all(|&w| w >= near) {
+                triangles.push([
+                    transformed_vertices[i].clone(),
+                    transformed_vertices[i + 1].clone(),
+                    transformed_vertices[i + 2].clone(),
+                ]);
+            } else if w.iter().any(|&w| w >= near) {
+                let tri = [&vertex_array[i], &vertex_array[i + 1], &vertex_array[i + 2]];
+                let polygon: Vec<Vertex> = clip_near(tri, w, near).iter().map(|vertex| vertex_shader(vertex, uniforms)).collect();
+                for k in 1..polygon.len() - 1 {
+                    triangles.push([polygon[0].clone(), polygon[k].clone(), polygon[k + 1].clone()]);
+                }
+            }
+        }
+    }
+
+    // En la vista plana cada triángulo usa el promedio de las normales de sus vértices
+    if uniforms.debug_view == DebugView::Flat {
+        for tri in &mut triangles {
+            let normal = (tri[0].transformed_normal + tri[1].transformed_normal + tri[2].transformed_normal).normalize();
+            for vertex in tri.iter_mut() {
+                vertex.transformed_normal = normal;
+            }
         }
     }
 
     // Rasterization Stage
+    let samples = framebuffer.sample_offsets().to_vec();
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let start = Instant::now();
     let mut fragments = Vec::new();
     for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+        triangle(&tri[0], &tri[1], &tri[2], &samples, width, height, &mut fragments);
     }
 
+    // El modo logarítmico toma aquí el logaritmo de la profundidad interpolada
+    for fragment in &mut fragments {
+        (fragment.depth, fragment.depth_slope) = uniforms.depth.fragment_depth(fragment.depth, fragment.depth_slope);
+    }
+
+    let stats = &mut framebuffer.raster_stats;
+    stats.time += start.elapsed();
+    stats.triangles += triangles.len();
+    stats.fragments += fragments.len();
+
+    match uniforms.debug_view {
+        DebugView::Wireframe => {
+            let stored = |vertex: &Vertex| {
+                let position = vertex.transformed_position;
+                Vec3::new(position.x, position.y, uniforms.depth.fragment_depth(position.z, Vec2::zeros()).0)
+            };
+            for tri in &triangles {
+                framebuffer.record_wireframe([stored(&tri[0]), stored(&tri[1]), stored(&tri[2])]);
+            }
+        }
+        // Solo importa cuántos fragmentos llegan a cada pixel, no hace falta sombrearlos
+        DebugView::Overdraw => {
+            for fragment in &fragments {
+                framebuffer.count_overdraw(fragment.position.x as usize, fragment.position.y as usize);
+            }
+            fragments.clear();
+        }
+        _ => {}
+    }
+
+    fragments
+}
+
+fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], shader: usize) {
     // Fragment Processing Stage
-    for fragment in fragments {
+    for fragment in rasterize(framebuffer, uniforms, vertex_array) {
         let x = fragment.position.x as usize;
         let y = fragment.position.y as usize;
         if x < framebuffer.width && y < framebuffer.height {
             // Apply fragment shader
-            let shaded_color = fragment_shader(&fragment, &uniforms, index);
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+            let shaded_color = if uniforms.debug_view.is_shaded() {
+                fragment_shader(&fragment, uniforms, shader)
+            } else {
+                uniforms.debug_view.shade(&fragment, uniforms)
+            };
+            framebuffer.set_current_color(shaded_color);
+            framebuffer.point_coverage(x, y, fragment.depth, fragment.depth_slope, fragment.coverage);
+        }
+    }
+}
+
+// Anillos translúcidos: se mezclan sobre el planeta y el fondo según la densidad del perfil
+fn render_ring(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex]) {
+    if !uniforms.debug_view.draws_translucent() {
+        return;
+    }
+    for fragment in rasterize(framebuffer, uniforms, vertex_array) {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+        if x < framebuffer.width && y < framebuffer.height {
+            let (color, alpha) = ring_shader(&fragment, uniforms);
+            let alpha = alpha * fragment.coverage_fraction(framebuffer.samples());
+            if alpha > 0.0 {
+                framebuffer.push_translucent(x, y, fragment.depth, color, alpha, BlendMode::Alpha);
+            }
+        }
+    }
+}
+
+// Capa de atmósfera: se rasteriza una esfera algo mayor que el planeta y cada fragmento
+// integra la dispersión a lo largo del rayo de la cámara; se mezcla en el pase de transparencias
+fn render_atmosphere(
+    framebuffer: &mut Framebuffer,
+    uniforms: &Uniforms,
+    vertex_array: &[Vertex],
+    atmosphere: &Atmosphere,
+    eye: Vec3,
+) {
+    if !uniforms.debug_view.draws_translucent() {
+        return;
+    }
+    let planet_center = uniforms.model_matrix.transform_point(&Point3::origin()).coords;
+    let shell_radius = (uniforms.model_matrix * Vec4::new(1.0, 0.0, 0.0, 0.0)).xyz().magnitude();
+    let planet_radius = shell_radius / (1.0 + atmosphere.height);
+
+    for fragment in rasterize(framebuffer, uniforms, vertex_array) {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+        if x >= framebuffer.width || y >= framebuffer.height {
+            continue;
+        }
+
+        // Solo la cara frontal del cascarón; la trasera ya queda cubierta por la integración
+        let world_position = uniforms.model_matrix.transform_point(&Point3::from(fragment.vertex_position)).coords;
+        let to_eye = eye - world_position;
+        if fragment.normal.dot(&to_eye) <= 0.0 {
+            continue;
+        }
+
+        let direction = -to_eye.normalize();
+        let (light, transmittance) = atmosphere.scatter(eye, direction, planet_center, planet_radius, uniforms.sun_position);
+
+        // Primero se atenúa lo que hay detrás y luego se suma la luz dispersada
+        let coverage = fragment.coverage_fraction(framebuffer.samples());
+        let filter = Color::from_float(transmittance.x, transmittance.y, transmittance.z);
+        framebuffer.push_translucent(x, y, fragment.depth, filter, coverage, BlendMode::Multiply);
+        framebuffer.push_translucent(x, y, fragment.depth, Color::from_float(light.x, light.y, light.z), coverage, BlendMode::Additive);
+    }
+}
+
+// Sistema que se simula: el ficticio de siempre o el sistema solar real en una de sus dos escalas
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Preset {
+    Fictional,
+    Solar(SystemScale),
+}
+
+impl Preset {
+    // Sección [system]: `preset` es fictional o solar y `scale`, true o readable
+    fn from_settings(settings: &Settings) -> Self {
+        let scale = match settings.get("system.scale") {
+            Some(name) => SystemScale::parse(name).unwrap_or_else(|| {
+                eprintln!("Escala desconocida: {}", name);
+                SystemScale::Readable
+            }),
+            None => SystemScale::Readable,
+        };
+        match settings.get("system.preset").map(|name| name.trim().to_lowercase()) {
+            Some(name) if name == "solar" => Preset::Solar(scale),
+            Some(name) if name != "fictional" => {
+                eprintln!("Sistema desconocido: {}", name);
+                Preset::Fictional
+            }
+            _ => Preset::Fictional,
+        }
+    }
+}
+
+// Recursos que se cargan una sola vez y se comparten entre cuadros
+struct Scene {
+    solar_system: Vec<Cuerpo>,
+    ship: Vec<Vertex>,
+    sky: Starfield,
+    preset: Preset,
+    epoch: f64,         // Días desde J2000 en el paso cero
+    days_per_step: f64,
+    saturn_rings: Option<String>, // Perfil radial de los anillos de Saturno en el sistema real
+}
+
+impl Scene {
+    // La semilla fija la fase de las órbitas, las velocidades de rotación y las estrellas. Los dos sistemas
+    // arrancan en `system.epoch`, una fecha o un día juliano, y avanzan `system.days_per_step` días por paso.
+    fn new(seed: u64, settings: &Settings) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let preset = Preset::from_settings(settings);
+        let saturn_rings = settings.get("system.saturn_rings").map(str::to_string);
+        let solar_system = match preset {
+            Preset::Fictional => create_solar_system(&mut rng),
+            Preset::Solar(scale) => solarsystem::create(scale, saturn_rings.as_deref()),
+        };
+        let epoch = match settings.get("system.epoch") {
+            Some(text) => calendar::parse_instant(text).unwrap_or_else(|| {
+                eprintln!("Fecha desconocida: {}", text);
+                ephemeris::J2000
+            }),
+            None => ephemeris::J2000,
+        };
+        let days_per_step = settings.get_f64("system.days_per_step", 1.0);
+        let sky = create_sky(&mut rng, settings);
+        let ship = Obj::load("assets/models/nave.obj").expect("Failed to load obj").get_vertex_array();
+        Scene { solar_system, ship, sky, preset, epoch: epoch - ephemeris::J2000, days_per_step, saturn_rings }
+    }
+
+    // Cuánto más grande es el sistema que el ficticio o el real en escala legible, para los que tienen
+    // las distancias de la cámara y de las etiquetas
+    fn distance_scale(&self) -> f64 {
+        match self.preset {
+            Preset::Fictional => 1.0,
+            Preset::Solar(scale) => scale.distance_scale(),
+        }
+    }
+
+    // Pasa el sistema real a la otra escala; devuelve cuánto cambiaron las distancias
+    fn toggle_scale(&mut self) -> Option<f64> {
+        let Preset::Solar(scale) = self.preset else {
+            return None;
+        };
+        let next = scale.next();
+        self.solar_system = solarsystem::create(next, self.saturn_rings.as_deref());
+        self.preset = Preset::Solar(next);
+        Some(next.distance_scale() / scale.distance_scale())
+    }
+
+    // Cada cuerpo va después de aquel alrededor del cual orbita, así que alcanza con una pasada
+    fn body_positions(&self, days: f64) -> Vec<DVec3> {
+        let mut positions: Vec<DVec3> = Vec::with_capacity(self.solar_system.len());
+        for body in &self.solar_system {
+            let center = body.parent.map_or(DVec3::zeros(), |parent| positions[parent]);
+            positions.push(center + body.orbit.offset(days));
+        }
+        positions
+    }
+
+    fn position(&self, index: usize, days: f64) -> DVec3 {
+        let body = &self.solar_system[index];
+        self.parent_position(index, days) + body.orbit.offset(days)
+    }
+
+    // Posición del cuerpo alrededor del cual orbita `index`; sin él, el origen
+    fn parent_position(&self, index: usize, days: f64) -> DVec3 {
+        self.solar_system[index].parent.map_or(DVec3::zeros(), |parent| self.position(parent, days))
+    }
+
+    fn orbit_centers(&self, days: f64) -> Vec<DVec3> {
+        let positions = self.body_positions(days);
+        self.solar_system.iter().map(|body| body.parent.map_or(DVec3::zeros(), |parent| positions[parent])).collect()
+    }
+
+    // El cuerpo más cercano a la dirección en que mira la cámara, dentro de un cono de 30°
+    fn aimed_body(&self, camera: &Camera, days: f64) -> Option<usize> {
+        let forward = camera.forward();
+        let max_angle = 30f32.to_radians();
+        self.body_positions(days)
+            .iter()
+            .map(|position| forward.dot(&camera.relative(position).normalize()).clamp(-1.0, 1.0).acos())
+            .enumerate()
+            .filter(|(_, angle)| *angle <= max_angle)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    // Cuerpo bajo el punto (x, y) de la pantalla; los que se dejaron de dibujar por cercanía no cuentan.
+    // El rayo y las matrices de los modelos están en el espacio relativo al ojo.
+    fn pick(&self, camera: &Camera, x: f32, y: f32, width: f32, height: f32, days: f64) -> Option<usize> {
+        let projection_matrix = create_perspective_matrix(width, height, camera);
+        let ray = camera.screen_ray(x, y, width, height, &projection_matrix);
+        let positions = self.body_positions(days);
+        let candidates: Vec<(usize, &Cuerpo, Mat4)> = self
+            .solar_system
+            .iter()
+            .zip(&positions)
+            .enumerate()
+            .map(|(index, (body, position))| (index, body, camera.relative(position)))
+            .filter(|(_, body, translation)| !body.is_too_close(*translation, Vec3::zeros()))
+            .map(|(index, body, translation)| (index, body, body.model_matrix(translation, days)))
+            .collect();
+        // Seis pixeles de tolerancia para los cuerpos diminutos
+        let tolerance = 6.0 * 2.0 * (camera.fov / 2.0).tan() / height;
+        picking::pick(&ray, &candidates, tolerance)
+    }
+
+    fn target_info(&self, index: usize, camera: &Camera, days: f64, selected: bool) -> Target<'_> {
+        let body = &self.solar_system[index];
+        let position = self.position(index, days);
+        Target {
+            name: &body.name,
+            selected,
+            distance: (position - camera.eye).magnitude() as f32,
+            speed: (self.position(index, days + self.days_per_step) - position).magnitude() as f32,
+            radius: body.scale,
+            orbit_radius: (position - self.parent_position(index, days)).magnitude() as f32,
+        }
+    }
+
+    fn follow(&self, camera: &Camera, index: usize, days: f64, frame: ReferenceFrame, views: &Viewpoints) -> Follow {
+        let body = &self.solar_system[index];
+        let position = self.position(index, days);
+        Follow::start(camera, index, body, position, self.parent_position(index, days), frame, views)
+    }
+
+    // La selección tiene prioridad sobre el cuerpo al que apunta la cámara
+    fn target(&self, camera: &Camera, days: f64, selected: Option<usize>) -> Option<Target<'_>> {
+        match selected {
+            Some(index) => Some(self.target_info(index, camera, days, true)),
+            None => self.aimed_body(camera, days).map(|index| self.target_info(index, camera, days, false)),
+        }
+    }
+}
+
+// Cadena completa de un cuadro: geometría, transparencias, antialiasing, bloom, tonos y postproceso
+struct Renderer {
+    framebuffer: Framebuffer,
+    supersampled: Option<Framebuffer>, // Buffer de mayor resolución cuando se usa SSAA
+    upscaled: Option<Framebuffer>,     // Tamaño de la ventana cuando `render_scale` es menor que 1
+    render_scale: f32,
+    anti_aliasing: AntiAliasing,
+    debug_view: DebugView,
+    tone_mapping: ToneMapping,
+    bloom: Bloom,
+    post_stack: PostStack,
+    overlay: Overlay,
+    labels: Labels,
+    selected: Option<usize>, // Cuerpo elegido con el ratón, se resalta en pantalla
+    raster_stats: RasterStats, // Del último cuadro
+}
+
+impl Renderer {
+    fn new(width: usize, height: usize, scene: &Scene, settings: &Settings) -> Self {
+        let anti_aliasing = match settings.get("anti_aliasing") {
+            Some(name) => AntiAliasing::parse(name).unwrap_or_else(|| {
+                eprintln!("Modo de antialiasing desconocido: {}", name);
+                AntiAliasing::Off
+            }),
+            None => AntiAliasing::Off,
+        };
+        let debug_view = match settings.get("debug_view") {
+            Some(name) => DebugView::parse(name).unwrap_or_else(|| {
+                eprintln!("Vista de depuración desconocida: {}", name);
+                DebugView::Shaded
+            }),
+            None => DebugView::Shaded,
+        };
+
+        let mut renderer = Renderer {
+            framebuffer: Framebuffer::new(1, 1),
+            supersampled: None,
+            upscaled: None,
+            render_scale: settings.get_f32("window.render_scale", 1.0).clamp(0.1, 1.0),
+            anti_aliasing,
+            debug_view,
+            tone_mapping: ToneMapping::new(ToneMapOperator::Clamp, 0.0),
+            bloom: Bloom::new(1.0, 0.5, 0.8, 5),
+            post_stack: PostStack::from_settings(settings),
+            overlay: Overlay::from_settings(&scene.solar_system, settings),
+            labels: Labels::from_settings(settings),
+            selected: None,
+            raster_stats: RasterStats::default(),
+        };
+        renderer.resize(width, height);
+        renderer
+    }
+
+    // Rehace los buffers para una ventana de `width` x `height`; la escena se dibuja a `render_scale`
+    // de ese tamaño y se agranda antes de las etiquetas y el HUD, que siempre van a la resolución de la ventana
+    fn resize(&mut self, width: usize, height: usize) {
+        let render_width = ((width as f32 * self.render_scale).round() as usize).max(1);
+        let render_height = ((height as f32 * self.render_scale).round() as usize).max(1);
+        self.framebuffer = Framebuffer::new(render_width, render_height);
+        self.framebuffer.set_background_color(0x000000); // Fondo negro
+        self.upscaled = ((render_width, render_height) != (width, height)).then(|| Framebuffer::new(width, height));
+        self.set_anti_aliasing(self.anti_aliasing);
+    }
+
+    // Lo que se muestra en la ventana
+    fn screen(&mut self) -> &mut Framebuffer {
+        self.upscaled.as_mut().unwrap_or(&mut self.framebuffer)
+    }
+
+    fn screen_size(&self) -> (usize, usize) {
+        let screen = self.upscaled.as_ref().unwrap_or(&self.framebuffer);
+        (screen.width, screen.height)
+    }
+
+    fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        self.anti_aliasing = anti_aliasing;
+        self.framebuffer.set_sample_pattern(&anti_aliasing.sample_pattern());
+        self.supersampled = match anti_aliasing {
+            AntiAliasing::Ssaa(_) => {
+                let scale = anti_aliasing.render_scale();
+                let width = (self.framebuffer.width as f32 * scale).round() as usize;
+                let height = (self.framebuffer.height as f32 * scale).round() as usize;
+                let mut supersampled = Framebuffer::new(width, height);
+                supersampled.set_background_color(0x000000);
+                Some(supersampled)
+            }
+            _ => None,
+        };
+    }
+
+    // `days` mueve la escena; `frame` solo lo usa el postproceso
+    fn draw(&mut self, scene: &Scene, camera: &Camera, ship_matrix: &Mat4, days: f64, frame: u32) {
+        let scale = self.anti_aliasing.render_scale();
+        let target = match &mut self.supersampled {
+            Some(supersampled) => supersampled,
+            None => &mut self.framebuffer,
+        };
+
+        target.clear();
+        target.depth = camera.depth_range();
+        let view_matrix = camera.view_matrix();
+        let projection_matrix = create_perspective_matrix(target.width as f32, target.height as f32, camera);
+        if self.debug_view.is_shaded() {
+            scene.sky.draw(target, &view_matrix, &projection_matrix, scale * scale);
+        }
+
+        draw_scene(target, scene, camera, ship_matrix, days, self.debug_view);
+        self.raster_stats = target.raster_stats;
+
+        // Las capas translúcidas se mezclan una vez que toda la geometría opaca está dibujada
+        target.resolve_samples();
+        target.resolve_translucent();
+        match self.debug_view {
+            DebugView::Wireframe => target.draw_wireframe(&LineStyle::solid(Color::from_float(0.2, 1.0, 0.4), 0.8)),
+            DebugView::Depth => target.show_depth(),
+            DebugView::Overdraw => target.show_overdraw(),
+            _ => {}
+        }
+        if let Some(supersampled) = &self.supersampled {
+            supersampled.downsample_into(&mut self.framebuffer);
+        }
+
+        // Las vistas de depuración se muestran tal cual, sin bloom, exposición ni efectos
+        let view_projection = create_perspective_matrix(self.framebuffer.width as f32, self.framebuffer.height as f32, camera) * view_matrix;
+        if self.debug_view.is_shaded() {
+            self.bloom.apply(&mut self.framebuffer);
+            self.framebuffer.tone_map(&self.tone_mapping);
+
+            // Las órbitas y estelas van sobre el cuadro ya mapeado para que el bloom y la exposición no las alteren
+            let centers = scene.orbit_centers(days);
+            self.overlay.draw(&mut self.framebuffer, &scene.solar_system, &centers, days, &view_projection, &camera.eye);
+
+            if self.anti_aliasing == AntiAliasing::Fxaa {
+                Fxaa.apply(&mut self.framebuffer, frame);
+            }
+            self.post_stack.apply(&mut self.framebuffer, frame);
+        }
+        if let Some(upscaled) = &mut self.upscaled {
+            self.framebuffer.upscale_into(upscaled);
+        }
+        let screen = self.upscaled.as_mut().unwrap_or(&mut self.framebuffer);
+
+        // Las etiquetas van a la resolución de la ventana y se ocultan con el zbuffer de la escena: las
+        // guías se mezclan antes de presentar el cuadro y los nombres se escriben sobre él
+        let positions: Vec<Vec3> = scene.body_positions(days).iter().map(|position| camera.relative(position)).collect();
+        let placed = self.labels.place(screen, &scene.solar_system, &positions, &view_projection, Vec3::zeros());
+        labels::draw_leaders(screen, &placed);
+        if let Some(index) = self.selected {
+            labels::draw_highlight(screen, &scene.solar_system[index], positions[index], &view_projection, Vec3::zeros());
+        }
+        screen.present();
+        labels::draw_names(screen, &placed);
+    }
+}
+
+// Dibuja la geometría del sistema solar y la nave; las matrices de proyección y viewport
+// salen del tamaño del framebuffer para que sirvan igual con supersampling. Todo se dibuja relativo
+// al ojo, con las posiciones restadas en f64, así que la nave (`ship_matrix`) también viene así.
+fn draw_scene(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, ship_matrix: &Mat4, days: f64, debug_view: DebugView) {
+    let projection_matrix = create_perspective_matrix(framebuffer.width as f32, framebuffer.height as f32, camera);
+    let viewport_matrix = create_viewport_matrix(framebuffer.width as f32, framebuffer.height as f32);
+    let view_matrix = camera.view_matrix();
+    let depth = camera.depth_range();
+
+    // El sol es la fuente de luz de toda la escena
+    let positions = scene.body_positions(days);
+    let sun_position = camera.relative(&positions[0]);
+
+    let surface_days = days.rem_euclid(SURFACE_PERIOD) as f32;
+    for (index, body) in scene.solar_system.iter().enumerate() {
+        let noise = create_noise(index);
+
+        let mut uniforms = Uniforms { 
+            model_matrix: Mat4::identity(), 
+            view_matrix, 
+            projection_matrix, 
+            viewport_matrix, 
+            days: surface_days,
+            noise,
+            material: body.material,
+            sun_position,
+            ring: body.ring.as_ref(),
+            debug_view,
+            depth,
+        };
+
+        // Posición orbital
+        let translation = camera.relative(&positions[index]);
+
+        // Rotación sobre su propio eje
+        let rotation_matrix = body.spin_matrix(days);
+
+        // Crear la model matrix combinando traslación, rotación y escala
+        let translation_matrix = Mat4::new_translation(&translation);
+        let scale_matrix = Mat4::new_scaling(body.scale);
+
+        uniforms.model_matrix = body.model_matrix(translation, days);
+
+        if body.is_too_close(translation, Vec3::zeros()) {
+            continue;
+        }
+
+        // El planeta, su atmósfera y sus anillos se descartan cada uno con su propia esfera envolvente
+        let radius = body.bounding_radius();
+        let in_camera = |radius: f32| is_in_camera(&translation, radius, &uniforms.view_matrix, &uniforms.projection_matrix, &depth);
+
+        if in_camera(radius) {
+            render(framebuffer, &uniforms, &body.vertex_array, body.shader);
+        }
+
+        if let Some(atmosphere) = &body.atmosphere {
+            if in_camera(radius * (1.0 + atmosphere.height)) {
+                let shell_scale = Mat4::new_scaling(body.scale * (1.0 + atmosphere.height));
+                uniforms.model_matrix = translation_matrix * rotation_matrix * shell_scale;
+                render_atmosphere(framebuffer, &uniforms, &body.vertex_array, atmosphere, Vec3::zeros());
+            }
+        }
+
+        if let Some(ring) = &body.ring {
+            if in_camera(body.scale * ring.outer_radius) {
+                // Los anillos no giran con el planeta, solo conservan su inclinación
+                let ring_uniforms = Uniforms {
+                    model_matrix: translation_matrix * ring.tilt_matrix() * scale_matrix,
+                    view_matrix,
+                    projection_matrix,
+                    viewport_matrix,
+                    days: surface_days,
+                    noise: create_noise(index),
+                    material: body.material,
+                    sun_position,
+                    ring: Some(ring),
+                    debug_view,
+                    depth,
+                };
+                render_ring(framebuffer, &ring_uniforms, &ring.vertex_array);
+            }
         }
     }
+
+    let uniforms = Uniforms { 
+        model_matrix: *ship_matrix, 
+        view_matrix, 
+        projection_matrix, 
+        viewport_matrix, 
+        days: surface_days,
+        noise: create_noise(6),
+        material: Material::new(0.2, 0.0),
+        sun_position,
+        ring: None,
+        debug_view,
+        depth,
+    };
+
+    render(framebuffer, &uniforms, &scene.ship, 7);
 }
 
 fn main() {
-    let window_width = 800;
-    let window_height = 600;
-    let framebuffer_width = 800;
-    let framebuffer_height = 600;
-    let disappearance_buffer = 5.0;  
-    let stars = generate_stars(500, framebuffer_width, framebuffer_height);
-    
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    let mut settings = Settings::load_or_default("settings.ini");
+    if let Err(error) = settings.apply_args(std::env::args().skip(1)) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+
+    // Sin ventana la semilla es fija por defecto para que las mediciones sean comparables
+    let headless = settings.get_bool("headless", false);
+    let seed = match settings.get("seed") {
+        Some(_) => settings.get_usize("seed", 0) as u64,
+        None if headless => 0,
+        None => rand::random(),
+    };
+
+    let (mut window_width, mut window_height) = settings.get_size("window.resolution").unwrap_or((800, 600));
+    let mut scene = Scene::new(seed, &settings);
+    let mut renderer = Renderer::new(window_width, window_height, &scene, &settings);
+
+    let camera_path = match settings.get("path.file") {
+        Some(filename) => match CameraPath::load(filename) {
+            Ok(path) => {
+                renderer.overlay.set_path(&path);
+                Some(path)
+            }
+            Err(error) => {
+                eprintln!("No se pudo leer el recorrido: {}", error);
+                None
+            }
+        },
+        None => None,
+    };
+
+    if headless {
+        run_headless(&settings, &scene, &mut renderer, camera_path.as_ref());
+        return;
+    }
+
+    // minifb no tiene pantalla completa exclusiva: se emula con una ventana sin bordes ni título en la
+    // esquina de la pantalla, del tamaño de `resolution`
+    let fullscreen = settings.get_bool("window.fullscreen", false);
     let mut window = Window::new(
         "Proyecto-SistemaSolar",
         window_width,
         window_height,
-        WindowOptions::default(),
+        WindowOptions {
+            resize: settings.get_bool("window.resizable", true),
+            borderless: fullscreen,
+            title: !fullscreen,
+            topmost: fullscreen,
+            ..WindowOptions::default()
+        },
     )
     .unwrap();
 
-    window.set_position(500, 500);
+    if fullscreen {
+        window.set_position(0, 0);
+    } else {
+        window.set_position(500, 500);
+    }
     window.update();
-
-    framebuffer.set_background_color(0x000000); // Fondo negro
+    let mut date_prompt = DatePrompt::new(&mut window);
 
     // Camera parameters
     let mut camera = Camera::new(
-        Vec3::new(0.0, 0.0, 20.0),
-        Vec3::new(0.0, 0.0, 0.0),
+        DVec3::new(0.0, 0.0, 20.0),
+        DVec3::new(0.0, 0.0, 0.0),
         Vec3::new(0.0, 1.0, 0.0)
     );
+    camera.set_projection(&settings);
+
+    let mut days = scene.epoch;
+    let mut frame: u32 = 0;
+    let mut time_speed = 1;
+    let mut paused = false;
+    let mut hud = Hud::from_settings(&settings);
+    let mut last_frame = Instant::now();
+    let mut mouse_was_down = false;
+    let mut mode = CameraMode::Orbit;
+    let flight = Flight::from_settings(&settings);
+    let mut last_mouse: Option<(f32, f32)> = None;
+    let mut last_update = Instant::now();
+    let mut reference_frame = match settings.get("camera.frame") {
+        Some(name) => ReferenceFrame::parse(name).unwrap_or_else(|| {
+            eprintln!("Marco de referencia desconocido: {}", name);
+            ReferenceFrame::Inertial
+        }),
+        None => ReferenceFrame::Inertial,
+    };
+    let mut views = Viewpoints::from_settings(&settings, Viewpoint::from_camera(&camera));
+    rescale_view(&mut camera, &mut views, &mut renderer.labels, scene.distance_scale());
+    let mut transition: Option<Transition> = None;
 
-    let mut translation_nave = Vec3::new(1.5, 1.5, 19.0);
-    let mut rotation_nave = Vec3::new(0.0, 1.0, 0.0);
-    let scale = 0.03f32;
-
-    let solar_system = create_solar_system();
-    let mut time = 0;
-    let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-    let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
-    
-    // Parámetro para la velocidad de rotación
-    let orbital_speed = 0.01;  // Controla la velocidad de rotación de los planetas
-    
     while window.is_open() {
-        if window.is_key_down(Key::Escape) {
+        // Con la línea de fecha abierta, Esc solo la cierra
+        if !date_prompt.is_open() && window.is_key_pressed(Key::Escape, KeyRepeat::No) {
             break;
         }
 
-        time += 1;
+        // Si cambió el tamaño de la ventana se rehacen los buffers; la proyección y el viewport salen de
+        // ellos en cada cuadro. Minimizada, la ventana puede medir cero.
+        let (width, height) = window.get_size();
+        if (width, height) != (window_width, window_height) && width > 0 && height > 0 {
+            window_width = width;
+            window_height = height;
+            renderer.resize(width, height);
+        }
 
-        handle_input(&window, &mut camera, &mut translation_nave, &mut rotation_nave);
+        // Segundos desde el cuadro anterior para el vuelo libre; se acota para que una pausa larga no lo dispare
+        let dt = last_update.elapsed().as_secs_f32().min(0.1);
+        last_update = Instant::now();
 
-        framebuffer.clear();
-        framebuffer.draw_stars(&stars); 
+        // Intro abre la línea para saltar a una fecha; mientras está abierta, el teclado escribe en ella.
+        // Las posiciones se recalculan solas a partir de la fecha nueva, pero las estelas ya no sirven.
+        if let Some(julian_day) = date_prompt.update(&window) {
+            days = julian_day - ephemeris::J2000;
+            renderer.overlay.clear_trails();
+            hud.notify(format!("Fecha: {} TDB", CalendarDate::from_julian_day(julian_day)));
+        }
+        let typing = date_prompt.is_open();
 
-        for (index, body) in solar_system.iter().enumerate() {
-            let noise = create_noise(index);
-            
-            let mut uniforms = Uniforms { 
-                model_matrix: Mat4::identity(), 
-                view_matrix: Mat4::identity(), 
-                projection_matrix, 
-                viewport_matrix, 
-                time, 
-                noise 
-            };
-        
-            // Posición orbital
-            let radius = body.orbit_radius;
-            let angle = time as f32 * orbital_speed + body.phase_offset;
-            let x_position = radius * angle.cos();
-            let z_position = radius * angle.sin();
-            let translation = Vec3::new(x_position, 0.0, z_position);
-        
-            // Rotación sobre su propio eje
-            let rotation_angle = time as f32 * body.rotation_speed; // Calcula el ángulo de rotación
-            let rotation_matrix = Mat4::from_axis_angle(&Vec3::y_axis(), rotation_angle); // Rotación en el eje Y
-        
-            // Crear la model matrix combinando traslación, rotación y escala
-            let translation_matrix = Mat4::new_translation(&translation);
-            let scale_matrix = Mat4::new_scaling(body.scale);
-        
-            uniforms.model_matrix = translation_matrix * rotation_matrix * scale_matrix;
-            uniforms.view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-            uniforms.time = time;
-        
-            let camera_to_planet_distance = (camera.eye - &translation).magnitude();
-            if camera_to_planet_distance <= body.scale + disappearance_buffer {
-                continue; 
+        if !typing {
+            handle_time_input(&window, &mut time_speed, &mut paused);
+        }
+        if !paused {
+            days += time_speed as f64 * scene.days_per_step;
+        }
+
+        if !typing {
+            // Un cambio de vista vuelve a la órbita; seguir un cuerpo o volar interrumpe la animación
+            if let Some(target) = handle_view_input(&window, &camera, &mut views, scene.distance_scale(), &mut hud) {
+                mode.enter_orbit(&camera);
+                transition = Some(views.transition(Viewpoint::from_camera(&camera), target));
+            }
+            if window.is_key_pressed(Key::C, KeyRepeat::No) {
+                if mode.is_free_fly() {
+                    if let Some(level) = mode.enter_orbit(&camera) {
+                        transition = Some(views.transition(Viewpoint::from_camera(&camera), level));
+                    }
+                } else {
+                    mode.enter_free_fly(&camera, flight);
+                }
+                hud.notify(format!("Cámara: {}", mode.name()));
+            }
+            if window.is_key_pressed(Key::Z, KeyRepeat::No) {
+                camera.depth = camera.depth.next();
+                hud.notify(format!("Profundidad: {}", camera.depth.name()));
+            }
+            // N pasa el sistema solar real de la escala legible a la real y al revés; un cuerpo seguido se
+            // vuelve a buscar en la escala nueva
+            if window.is_key_pressed(Key::N, KeyRepeat::No) {
+                match scene.toggle_scale() {
+                    Some(factor) => {
+                        transition = None;
+                        rescale_view(&mut camera, &mut views, &mut renderer.labels, factor);
+                        renderer.overlay.clear_trails();
+                        if let CameraMode::Follow(follow) = &mode {
+                            let target = follow.target;
+                            mode.enter_follow(scene.follow(&camera, target, days, reference_frame, &views));
+                        }
+                        if let Preset::Solar(scale) = scene.preset {
+                            hud.notify(format!("Escala: {}", scale.name()));
+                        }
+                    }
+                    None => hud.notify("Solo el sistema solar real cambia de escala (system.preset = solar)".to_string()),
+                }
+            }
+            // J reproduce el recorrido de cámara desde el principio o lo detiene
+            if window.is_key_pressed(Key::J, KeyRepeat::No) {
+                match &camera_path {
+                    _ if mode.is_path() => {
+                        if let Some(level) = mode.enter_orbit(&camera) {
+                            transition = Some(views.transition(Viewpoint::from_camera(&camera), level));
+                        }
+                    }
+                    Some(path) => mode.enter_path(Playback::start(path)),
+                    None => hud.notify("No hay un recorrido cargado (path.file en settings.ini)".to_string()),
+                }
+            }
+            let previous_frame = reference_frame;
+            if let Some(index) = handle_follow_input(&window, &scene, &camera, &mut mode, &mut reference_frame, renderer.selected, days) {
+                mode.enter_follow(scene.follow(&camera, index, days, reference_frame, &views));
+                renderer.selected = Some(index);
+            }
+            if reference_frame != previous_frame {
+                hud.notify(format!("Marco de referencia: {}", reference_frame.name()));
+            }
+        }
+        match &mut mode {
+            CameraMode::Follow(follow) => {
+                transition = None;
+                follow.update(&mut camera, scene.position(follow.target, days), scene.parent_position(follow.target, days));
+            }
+            CameraMode::FreeFly(free_fly) => {
+                transition = None;
+                let input = if typing { FlightInput::default() } else { handle_free_fly_input(&window, &mut last_mouse) };
+                free_fly.update(&mut camera, &input, dt);
+            }
+            CameraMode::Path(playback) => {
+                transition = None;
+                if let Some(path) = &camera_path {
+                    path.sample(playback.time()).apply(&mut camera);
+                }
+                // Al terminar, la cámara se queda donde la dejó el recorrido y vuelve a la órbita
+                if playback.time() >= playback.duration {
+                    if let Some(level) = mode.enter_orbit(&camera) {
+                        transition = Some(views.transition(Viewpoint::from_camera(&camera), level));
+                    }
+                }
+            }
+            CameraMode::Orbit => {}
+        }
+        if transition.as_ref().is_some_and(|transition| transition.update(&mut camera)) {
+            transition = None;
+        }
+
+        // La rueda acerca o aleja con el campo de visión; un recorrido trae el suyo
+        if let Some((_, scroll)) = window.get_scroll_wheel() {
+            if scroll != 0.0 && !mode.is_path() {
+                transition = None;
+                camera.zoom_fov(scroll.signum());
             }
-        
-            if is_in_camera(&translation, &uniforms.view_matrix, &uniforms.projection_matrix) {
-                render(&mut framebuffer, &uniforms, &body.vertex_array, index);
+        }
+
+        // Si el usuario mueve la cámara durante una transición, la toma en el punto en que está
+        if mode.uses_orbit_controls() && !typing {
+            let animated = Viewpoint::from_camera(&camera);
+            handle_input(&window, &mut camera);
+            if Viewpoint::from_camera(&camera) != animated {
+                transition = None;
             }
         }
+        // La nave va delante de la cámara en todos los modos
+        let (translation_nave, rotation_nave) = ship_in_front_of(&camera);
+        if !typing {
+            handle_tone_mapping_input(&window, &mut renderer.tone_mapping, &mut renderer.bloom, &mut hud);
+            handle_post_process_input(&window, &mut renderer.post_stack, &mut hud);
+            handle_anti_aliasing_input(&window, &mut renderer, &mut hud);
+            handle_overlay_input(&window, &mut renderer.overlay);
+            handle_debug_view_input(&window, &mut renderer.debug_view, &mut hud);
+            handle_hud_input(&window, &mut hud, &mut renderer.labels);
+        }
 
-        let noise = create_noise(6);
+        // Un clic elige el cuerpo bajo el cursor y lo pone en el centro de la cámara; en el vacío deselecciona.
+        // Durante el seguimiento, el cuerpo elegido pasa a ser el seguido; en vuelo libre o en un recorrido solo se selecciona.
+        let mouse_down = window.get_mouse_down(MouseButton::Left);
+        if mouse_down && !mouse_was_down {
+            if let Some((x, y)) = window.get_mouse_pos(MouseMode::Discard) {
+                renderer.selected = scene.pick(&camera, x, y, window_width as f32, window_height as f32, days);
+                match (renderer.selected, &mode) {
+                    (Some(index), CameraMode::Follow(_)) => mode.enter_follow(scene.follow(&camera, index, days, reference_frame, &views)),
+                    (Some(index), CameraMode::Orbit) => {
+                        transition = None;
+                        camera.center = scene.position(index, days);
+                    }
+                    (None, CameraMode::Follow(_)) => {
+                        mode.enter_orbit(&camera);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        mouse_was_down = mouse_down;
 
-        let mut uniforms = Uniforms { 
-            model_matrix: Mat4::identity(), 
-            view_matrix: Mat4::identity(), 
-            projection_matrix, 
-            viewport_matrix, 
-            time, 
-            noise 
+        if !paused {
+            renderer.overlay.record(&scene.body_positions(days), translation_nave);
+        }
+        let ship_matrix = create_model_matrix(camera.relative(&translation_nave), SHIP_SCALE, rotation_nave);
+        let render_start = Instant::now();
+        frame = frame.wrapping_add(1);
+        renderer.draw(&scene, &camera, &ship_matrix, days, frame);
+        hud.record_frame(last_frame.elapsed().as_secs_f32(), render_start.elapsed().as_secs_f32());
+        last_frame = Instant::now();
+
+        let info = HudInfo {
+            days,
+            time_speed,
+            paused,
+            camera_position: camera.eye,
+            fov: camera.fov,
+            depth: camera.depth_range(),
+            target: scene.target(&camera, days, renderer.selected),
+            camera_mode: mode.status(&scene.solar_system),
+            render_size: (renderer.framebuffer.width, renderer.framebuffer.height),
         };
+        hud.draw(renderer.screen(), &info);
+        match date_prompt.text() {
+            Some(text) => hud.draw_date_prompt(renderer.screen(), text, date_prompt.invalid),
+            None => hud.draw_message(renderer.screen()),
+        }
+
+        let (width, height) = renderer.screen_size();
+        window.update_with_buffer(&renderer.screen().buffer, width, height).unwrap();
+    }
+}
+
+// Modo sin ventana para medir tiempos y generar cuadros reproducibles, por ejemplo:
+//   --headless --frames 120 --anti_aliasing msaa4 --output cuadro.ppm --camera.eye 0,2,9
+// Con `--path.play` la cámara sigue el recorrido a `path.fps` cuadros por segundo de recorrido, sin
+// depender del reloj, y si `output` tiene `#` se guarda cada cuadro con su número: cuadros/####.ppm
+fn run_headless(settings: &Settings, scene: &Scene, renderer: &mut Renderer, camera_path: Option<&CameraPath>) {
+    let camera_path = camera_path.filter(|_| settings.get_bool("path.play", false));
+    let fps = settings.get_f32("path.fps", 30.0).max(1.0);
+    let frames = match camera_path {
+        Some(path) if settings.get("frames").is_none() => (path.duration() * fps).ceil() as usize + 1,
+        _ => settings.get_usize("frames", 60).max(1),
+    };
+    let output = settings.get("output");
+    let numbered = output.is_some_and(|output| output.contains('#'));
+
+    // Las posiciones que se pasan se usan tal cual; la inicial, el plano lejano y las etiquetas se llevan
+    // a la escala del sistema
+    let scale = scene.distance_scale();
+    let mut camera = Camera::new(
+        settings.get_dvec3("camera.eye").unwrap_or(DVec3::new(0.0, 0.0, 20.0 * scale)),
+        settings.get_dvec3("camera.center").unwrap_or(DVec3::new(0.0, 0.0, 0.0)),
+        Vec3::new(0.0, 1.0, 0.0)
+    );
+    camera.set_projection(settings);
+    camera.far *= scale as f32;
+    renderer.labels.fade_start *= scale as f32;
+    renderer.labels.fade_end *= scale as f32;
+
+    let mut frame_times = Vec::with_capacity(frames);
+    let mut raster_stats = RasterStats::default();
+    let mut hud = Hud::from_settings(settings);
+    for frame in 1..=frames {
+        let days = scene.epoch + frame as f64 * scene.days_per_step;
+        if let Some(path) = camera_path {
+            path.sample((frame - 1) as f32 / fps).apply(&mut camera);
+        }
+        let (translation_nave, rotation_nave) = ship_in_front_of(&camera);
+        let ship_matrix = create_model_matrix(camera.relative(&translation_nave), SHIP_SCALE, rotation_nave);
+
+        renderer.overlay.record(&scene.body_positions(days), translation_nave);
+        let start = Instant::now();
+        renderer.draw(scene, &camera, &ship_matrix, days, frame as u32);
+        let elapsed = start.elapsed();
+        frame_times.push(elapsed.as_secs_f64() * 1000.0);
+        hud.record_frame(elapsed.as_secs_f32(), elapsed.as_secs_f32());
+        raster_stats.add(&renderer.raster_stats);
+
+        if let Some(output) = output.filter(|_| numbered) {
+            let filename = numbered_filename(output, frame - 1);
+            if let Err(error) = renderer.screen().save_ppm(&filename) {
+                eprintln!("No se pudo guardar {}: {}", filename, error);
+            }
+        }
+    }
+
+    // El HUD muestra tiempos de reloj, así que solo se agrega a la imagen si se pide
+    if settings.get_bool("hud.headless", false) {
+        let days = scene.epoch + frames as f64 * scene.days_per_step;
+        let info = HudInfo {
+            days,
+            time_speed: 1,
+            paused: false,
+            camera_position: camera.eye,
+            fov: camera.fov,
+            depth: camera.depth_range(),
+            target: scene.target(&camera, days, renderer.selected),
+            camera_mode: CameraMode::Orbit.status(&scene.solar_system),
+            render_size: (renderer.framebuffer.width, renderer.framebuffer.height),
+        };
+        hud.draw(renderer.screen(), &info);
+    }
+
+    frame_times.sort_by(|a, b| a.total_cmp(b));
+    let average = frame_times.iter().sum::<f64>() / frames as f64;
+    println!(
+        "{} cuadros {}x{} (escena {}x{}), antialiasing {}: promedio {:.2} ms, mediana {:.2} ms, mínimo {:.2} ms",
+        frames,
+        renderer.screen_size().0,
+        renderer.screen_size().1,
+        renderer.framebuffer.width,
+        renderer.framebuffer.height,
+        renderer.anti_aliasing.name(),
+        average,
+        frame_times[frames / 2],
+        frame_times[0],
+    );
+    println!(
+        "rasterizador: {:.0} triángulos/s, {:.1} M fragmentos/s ({} triángulos y {} fragmentos por cuadro)",
+        raster_stats.triangles as f64 / raster_stats.time.as_secs_f64(),
+        raster_stats.fragments as f64 / raster_stats.time.as_secs_f64() / 1e6,
+        raster_stats.triangles / frames,
+        raster_stats.fragments / frames,
+    );
+
+    if let Some(output) = output.filter(|_| !numbered) {
+        if let Err(error) = renderer.screen().save_ppm(output) {
+            eprintln!("No se pudo guardar {}: {}", output, error);
+        }
+    }
+}
+
+// Reemplaza el primer grupo de `#` por el número de cuadro con tantas cifras como `#` haya
+fn numbered_filename(pattern: &str, frame: usize) -> String {
+    let start = pattern.find('#').unwrap_or(pattern.len());
+    let digits = pattern[start..].chars().take_while(|c| *c == '#').count();
+    format!("{}{:0width$}{}", &pattern[..start], frame, &pattern[start + digits..], width = digits)
+}
+
+// Antialiasing (X): recorre off, FXAA, MSAA y SSAA
+fn handle_anti_aliasing_input(window: &Window, renderer: &mut Renderer, hud: &mut Hud) {
+    if window.is_key_pressed(Key::X, KeyRepeat::No) {
+        let next = renderer.anti_aliasing.next();
+        renderer.set_anti_aliasing(next);
+        hud.notify(format!("Antialiasing: {}", next.name()));
+    }
+}
 
-        uniforms.model_matrix = create_model_matrix(translation_nave, scale, rotation_nave);
-        uniforms.view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
+// Operador de mapeo de tonos (T), exposición (+/-) y bloom (G)
+fn handle_tone_mapping_input(window: &Window, tone_mapping: &mut ToneMapping, bloom: &mut Bloom, hud: &mut Hud) {
+    let exposure_speed = 0.05;
 
-        let obj = Obj::load("assets/models/nave.obj").expect("Failed to load obj");
-        let vertex_arrays = obj.get_vertex_array(); 
+    if window.is_key_pressed(Key::T, KeyRepeat::No) {
+        tone_mapping.operator = tone_mapping.operator.next();
+        hud.notify(format!("Mapeo de tonos: {}", tone_mapping.operator.name()));
+    }
+    if window.is_key_pressed(Key::G, KeyRepeat::No) {
+        bloom.enabled = !bloom.enabled;
+    }
+    if window.is_key_down(Key::Equal) {
+        tone_mapping.exposure += exposure_speed;
+    }
+    if window.is_key_down(Key::Minus) {
+        tone_mapping.exposure -= exposure_speed;
+    }
+}
+
+// Las teclas 1-9 activan o desactivan los efectos de postproceso en el orden de la cadena
+fn handle_post_process_input(window: &Window, post_stack: &mut PostStack, hud: &mut Hud) {
+    if is_control_down(window) || is_alt_down(window) {
+        return;
+    }
+    let keys = [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9];
+    for (index, key) in keys.iter().enumerate() {
+        if window.is_key_pressed(*key, KeyRepeat::No) {
+            if let Some(status) = post_stack.toggle(index) {
+                hud.notify(status);
+            }
+        }
+    }
+}
+
+// Seguimiento: Tab pasa al siguiente cuerpo (Shift + Tab al anterior), F sigue o suelta el cuerpo
+// seleccionado o al que apunta la cámara, y R alterna entre el marco inercial y el corrotante.
+// Devuelve el cuerpo que hay que empezar a seguir.
+fn handle_follow_input(
+    window: &Window,
+    scene: &Scene,
+    camera: &Camera,
+    mode: &mut CameraMode,
+    reference_frame: &mut ReferenceFrame,
+    selected: Option<usize>,
+    days: f64,
+) -> Option<usize> {
+    let mut start = None;
+    let count = scene.solar_system.len();
+    if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+        let current = mode.follow().map(|follow| follow.target).or(selected);
+        start = Some(match current {
+            Some(index) if is_shift_down(window) => (index + count - 1) % count,
+            Some(index) => (index + 1) % count,
+            None => 0,
+        });
+    }
+    if window.is_key_pressed(Key::F, KeyRepeat::No) {
+        if mode.follow().is_some() {
+            mode.enter_orbit(camera);
+        } else {
+            start = selected.or_else(|| scene.aimed_body(camera, days));
+        }
+    }
+    if window.is_key_pressed(Key::R, KeyRepeat::No) {
+        *reference_frame = reference_frame.next();
+        if let Some(follow) = mode.follow_mut() {
+            follow.frame = *reference_frame;
+        }
+    }
+    start
+}
 
-        render(&mut framebuffer, &uniforms,&vertex_arrays, 7);
-        
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
+// Vuelo libre: W/S adelante y atrás, A/D a los lados, RePág/AvPág arriba y abajo, flechas para cabeceo
+// y guiñada, Q/E para el alabeo y Shift para ir más rápido. Con el botón derecho apretado, el ratón mira.
+fn handle_free_fly_input(window: &Window, last_mouse: &mut Option<(f32, f32)>) -> FlightInput {
+    let axis = |positive: Key, negative: Key| {
+        window.is_key_down(positive) as i32 as f32 - window.is_key_down(negative) as i32 as f32
+    };
+
+    let mut mouse = (0.0, 0.0);
+    if window.get_mouse_down(MouseButton::Right) {
+        let position = window.get_mouse_pos(MouseMode::Pass);
+        if let (Some((x, y)), Some((last_x, last_y))) = (position, *last_mouse) {
+            mouse = (x - last_x, y - last_y);
+        }
+        *last_mouse = position;
+    } else {
+        *last_mouse = None;
+    }
+
+    FlightInput {
+        thrust: Vec3::new(axis(Key::D, Key::A), axis(Key::PageUp, Key::PageDown), axis(Key::W, Key::S)),
+        turn: Vec3::new(axis(Key::Up, Key::Down), axis(Key::Right, Key::Left), axis(Key::E, Key::Q)),
+        mouse,
+        boost: is_shift_down(window),
+    }
+}
+
+// Velocidad de la simulación: coma la reduce a la mitad, punto la duplica y espacio pausa
+fn handle_time_input(window: &Window, time_speed: &mut u32, paused: &mut bool) {
+    if window.is_key_pressed(Key::Comma, KeyRepeat::No) {
+        *time_speed = (*time_speed / 2).max(1);
+    }
+    if window.is_key_pressed(Key::Period, KeyRepeat::No) {
+        *time_speed = (*time_speed * 2).min(64);
+    }
+    if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+        *paused = !*paused;
+    }
+}
+
+// HUD (I), panel de ayuda (H) y etiquetas de los cuerpos (L)
+fn handle_hud_input(window: &Window, hud: &mut Hud, labels: &mut Labels) {
+    if window.is_key_pressed(Key::L, KeyRepeat::No) {
+        labels.visible = !labels.visible;
+    }
+    if window.is_key_pressed(Key::I, KeyRepeat::No) {
+        hud.visible = !hud.visible;
+    }
+    if window.is_key_pressed(Key::H, KeyRepeat::No) {
+        hud.show_help = !hud.show_help;
+    }
+}
+
+// Vistas de depuración (F1-F6); la tecla de la vista activa vuelve al render normal
+fn handle_debug_view_input(window: &Window, debug_view: &mut DebugView, hud: &mut Hud) {
+    let keys = [Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6];
+    for (key, view) in keys.iter().zip(debugview::VIEWS) {
+        if window.is_key_pressed(*key, KeyRepeat::No) {
+            *debug_view = if *debug_view == view { DebugView::Shaded } else { view };
+            hud.notify(format!("Vista: {}", debug_view.name()));
+        }
+    }
+}
+
+// Órbitas (O), estelas (P), vista previa del recorrido de cámara (U) y, con Ctrl + 1-9, la superposición
+// de cada cuerpo del sistema solar
+fn handle_overlay_input(window: &Window, overlay: &mut Overlay) {
+    if window.is_key_pressed(Key::O, KeyRepeat::No) {
+        overlay.show_orbits = !overlay.show_orbits;
+    }
+    if window.is_key_pressed(Key::P, KeyRepeat::No) {
+        overlay.show_trails = !overlay.show_trails;
     }
+    if window.is_key_pressed(Key::U, KeyRepeat::No) {
+        overlay.show_path = !overlay.show_path;
+    }
+    if is_control_down(window) {
+        let keys = [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9];
+        for (index, key) in keys.iter().enumerate() {
+            if window.is_key_pressed(*key, KeyRepeat::No) {
+                overlay.toggle_body(index);
+            }
+        }
+    }
+}
+
+fn is_control_down(window: &Window) -> bool {
+    window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl)
+}
+
+fn is_alt_down(window: &Window) -> bool {
+    window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt)
+}
+
+fn is_shift_down(window: &Window) -> bool {
+    window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift)
 }
 
+// Vistas: B lleva a la vista superior, Inicio a la inicial y Alt + 1-9 a una vista con nombre; antes
+// de ir se apila la vista actual, y V vuelve a la anterior (o a la inicial si la pila está vacía).
+// K apila la vista actual sin moverse y Alt + Shift + 1-9 la guarda con nombre.
+// Devuelve la vista a la que hay que animar la cámara. La vista superior se aleja según `scale`, la
+// escala de distancias del sistema.
+fn handle_view_input(window: &Window, camera: &Camera, views: &mut Viewpoints, scale: f64, hud: &mut Hud) -> Option<Viewpoint> {
+    let current = Viewpoint::from_camera(camera);
+    let mut target = None;
+
+    if window.is_key_pressed(Key::B, KeyRepeat::No) {
+        // El eje "arriba" apunta hacia z negativo para ver el plano de las órbitas como un mapa
+        target = Some(Viewpoint {
+            eye: DVec3::new(0.0, 10.0 * scale, 0.0),
+            center: DVec3::new(0.0, 0.0, 0.0),
+            up: Vec3::new(0.0, 0.0, -1.0),
+            fov: current.fov,
+        });
+    }
+    if window.is_key_pressed(Key::Home, KeyRepeat::No) {
+        target = Some(views.home);
+    }
+    if is_alt_down(window) {
+        let keys = [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9];
+        for (slot, key) in keys.iter().enumerate().take(NAMED_SLOTS) {
+            if !window.is_key_pressed(*key, KeyRepeat::No) {
+                continue;
+            }
+            if is_shift_down(window) {
+                hud.notify(format!("Vista guardada: {}", views.save(slot, current)));
+            } else if let Some((name, viewpoint)) = views.named(slot) {
+                hud.notify(format!("Vista: {}", name));
+                target = Some(viewpoint);
+            }
+        }
+    }
+    if let Some(viewpoint) = target {
+        views.push(current);
+        return Some(viewpoint);
+    }
 
-fn handle_input(window: &Window, camera: &mut Camera, translation_nave: &mut Vec3, rotation_nave: &mut Vec3) {
+    if window.is_key_pressed(Key::K, KeyRepeat::No) {
+        views.push(current);
+    }
+    if window.is_key_pressed(Key::V, KeyRepeat::No) {
+        return Some(views.pop().unwrap_or(views.home));
+    }
+    None
+}
+
+fn handle_input(window: &Window, camera: &mut Camera) {
     let movement_speed = 1.0;
     let rotation_speed = PI / 50.0;
     let zoom_speed = 0.1;
     
-    // Inicializamos original_camera con los valores predeterminados
-    let mut original_camera: Option<(Vec3, Vec3, Vec3)> = Some((
-        Vec3::new(0.0, 0.0, 20.0), // Posición original de la cámara
-        Vec3::new(0.0, 0.0, 0.0),  // Centro de la cámara
-        Vec3::new(0.0, 1.0, 0.0),  // Dirección "arriba" de la cámara
-    ));
-
     // Cámara: controles de órbita
     if window.is_key_down(Key::Left) {
         camera.orbit(rotation_speed, 0.0);
@@ -481,74 +1660,49 @@ fn handle_input(window: &Window, camera: &mut Camera, translation_nave: &mut Vec
     if window.is_key_down(Key::Down) {
         camera.zoom(-zoom_speed);
     }
-
-    // Guardar la posición original de la cámara cuando se presiona 'B'
-    if window.is_key_down(Key::B) {
-        if original_camera.is_none() {
-            // Guardamos la cámara solo si no ha sido guardada antes
-            original_camera = Some((
-                camera.eye.clone(), 
-                camera.center.clone(), 
-                camera.up.clone()
-            ));
-        }
-        let solar_system_center = Vec3::new(0.0, 0.0, 0.0); // Asume que el centro del sistema solar es (0, 0, 0)
-        let bird_eye_height = 10.0; // Altura de la vista superior
-        camera.eye = Vec3::new(solar_system_center.x, bird_eye_height, solar_system_center.z);
-        camera.center = solar_system_center;
-        camera.up = Vec3::new(0.0, 0.0, -1.0); // Eje "arriba" apunta hacia el eje Z negativo
-    }
-
-    // Volver a la posición original (key 'V')
-    if window.is_key_down(Key::V) {
-        if let Some((original_eye, original_center, original_up)) = original_camera {
-            camera.eye = original_eye;
-            camera.center = original_center;
-            camera.up = original_up;
-            original_camera = None; // Restablecer la cámara original después de restaurarla
-        }
-    }
-
-    // Actualizar la posición de la nave basada en la posición de la cámara
-    let camera_direction = (camera.center - camera.eye).normalize();
-    *translation_nave = camera.eye + camera_direction * 1.0; // Mantener la nave a 2 unidades frente a la cámara
-    rotation_nave.y = camera_direction.y.atan2(camera_direction.x);  // La nave rota según el ángulo de la cámara
 }
 
-
-
-fn is_in_camera(position: &Vec3, view_matrix: &Mat4, projection_matrix: &Mat4) -> bool {
-    // Convertir la posición a Vec4 (con w = 1.0 para objetos estáticos)
-    let position_4d = Vec4::new(position.x, position.y, position.z, 1.0);
-
-    // Aplicar las transformaciones (vista + proyección)
-    let clip_space_pos = projection_matrix * view_matrix * position_4d;
-
-    // Normalizar a NDC
-    let x_ndc = clip_space_pos.x / clip_space_pos.w;
-    let y_ndc = clip_space_pos.y / clip_space_pos.w;
-    let z_ndc = clip_space_pos.z / clip_space_pos.w;
-
-    // Comprobar si la posición está dentro del frustum
-    // x, y dentro de [-1, 1] y z dentro de [0, 1]
-    x_ndc >= -1.0 && x_ndc <= 1.0 &&
-    y_ndc >= -1.0 && y_ndc <= 1.0 &&
-    z_ndc >= 0.0 && z_ndc <= 1.0
+// Lleva a otra escala lo que se mide en distancias de la escena: la cámara y su plano lejano, las vistas
+// guardadas y el desvanecido de las etiquetas
+fn rescale_view(camera: &mut Camera, views: &mut Viewpoints, labels: &mut Labels, factor: f64) {
+    camera.eye *= factor;
+    camera.center *= factor;
+    camera.far *= factor as f32;
+    views.rescale(factor);
+    labels.fade_start *= factor as f32;
+    labels.fade_end *= factor as f32;
 }
 
-fn generate_stars(num_stars: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
-    let mut rng = rand::thread_rng();
-    (0..num_stars)
-        .map(|_| {
-            (
-                rng.gen_range(0..width),  // Posición X de la estrella
-                rng.gen_range(0..height) // Posición Y de la estrella
-            )
-        })
-        .collect()
+fn ship_in_front_of(camera: &Camera) -> (DVec3, Vec3) {
+    let camera_direction = camera.forward();
+    let translation = camera.eye + camera_direction.cast() * 1.0; // Mantener la nave a 2 unidades frente a la cámara
+    let rotation = Vec3::new(0.0, camera_direction.y.atan2(camera_direction.x), 0.0);  // La nave rota según el ángulo de la cámara
+    (translation, rotation)
 }
 
 
 
-
-
+// Si alguna parte de la esfera de centro `center` y radio `radius` (en el espacio relativo al ojo) cae dentro
+// del frustum. Los planos laterales salen de las filas de vista-proyección; la distancia (w) se compara con
+// los planos cercano y lejano, sea cual sea el modo del zbuffer. Probar solo el centro haría desaparecer un
+// planeta visto de cerca en cuanto su centro sale de la pantalla.
+fn is_in_camera(center: &Vec3, radius: f32, view_matrix: &Mat4, projection_matrix: &Mat4, depth: &DepthRange) -> bool {
+    let view_projection = projection_matrix * view_matrix;
+    let w_row = view_projection.row(3).transpose();
+    let point = Vec4::new(center.x, center.y, center.z, 1.0);
+    let outside = |plane: Vec4| {
+        let length = plane.xyz().magnitude();
+        length > 0.0 && plane.dot(&point) / length < -radius
+    };
+    let sides = (0..2).flat_map(|axis| {
+        let row = view_projection.row(axis).transpose();
+        [w_row + row, w_row - row]
+    });
+    for plane in sides {
+        if outside(plane) {
+            return false;
+        }
+    }
+    let distance = w_row.dot(&point) / w_row.xyz().magnitude().max(f32::EPSILON);
+    distance + radius >= depth.near && distance - radius <= depth.far
+}
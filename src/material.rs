@@ -0,0 +1,17 @@
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    pub bump_strength: f32, // Cuánto inclina el relieve del ruido a la normal (0 = superficie lisa)
+    pub emission: f32,      // Multiplicador de la luz propia (sol, lava); lo que pase de 1.0 genera bloom
+}
+
+impl Material {
+    pub fn new(bump_strength: f32, emission: f32) -> Self {
+        Material { bump_strength, emission }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material { bump_strength: 0.0, emission: 0.0 }
+    }
+}
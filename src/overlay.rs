@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use nalgebra_glm::{DVec3, Mat4, Vec3, Vec4};
+use crate::camerapath::CameraPath;
+use crate::color::Color;
+use crate::cuerpo::Cuerpo;
+use crate::depth::DepthRange;
+use crate::framebuffer::{Framebuffer, LineStyle};
+use crate::settings::Settings;
+
+// Tramos con que se dibuja una órbita completa
+const ORBIT_SEGMENTS: usize = 128;
+// Muestras por segundo de recorrido con que se dibuja la vista previa de un recorrido de cámara
+const PATH_SAMPLES_PER_SECOND: f32 = 30.0;
+
+// Posiciones recientes de un objeto, de la más antigua a la más nueva
+pub struct Trail {
+    points: VecDeque<DVec3>,
+    capacity: usize,
+}
+
+impl Trail {
+    pub fn new(capacity: usize) -> Self {
+        Trail { points: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, point: DVec3) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.points.len() == self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+}
+
+// Órbitas previstas y estelas de los cuerpos y la nave, dibujadas encima del cuadro ya mapeado
+pub struct Overlay {
+    pub show_orbits: bool,
+    pub show_trails: bool,
+    pub orbit_alpha: f32,
+    pub trail_alpha: f32,
+    visible: Vec<bool>, // Por cuerpo, en el orden del sistema solar
+    trails: Vec<Trail>,
+    ship_trail: Trail,
+    pub show_path: bool,
+    path: Option<PathPreview>,
+}
+
+// Recorrido de cámara ya muestreado: la curva del ojo, la del objetivo y cada cuadro clave
+struct PathPreview {
+    eyes: Vec<DVec3>,
+    targets: Vec<DVec3>,
+    keyframes: Vec<(DVec3, DVec3)>,
+}
+
+impl Overlay {
+    // Lee la sección [overlay]; `hidden` es una lista de nombres de cuerpos que empiezan ocultos
+    pub fn from_settings(bodies: &[Cuerpo], settings: &Settings) -> Self {
+        let trail_length = settings.get_usize("overlay.trail_length", 120);
+        let hidden = settings.get_list("overlay.hidden").unwrap_or_default();
+
+        Overlay {
+            show_orbits: settings.get_bool("overlay.orbits", true),
+            show_trails: settings.get_bool("overlay.trails", false),
+            orbit_alpha: settings.get_f32("overlay.orbit_alpha", 0.35),
+            trail_alpha: settings.get_f32("overlay.trail_alpha", 0.8),
+            visible: bodies.iter().map(|body| !hidden.iter().any(|name| name.eq_ignore_ascii_case(&body.name))).collect(),
+            trails: bodies.iter().map(|_| Trail::new(trail_length)).collect(),
+            ship_trail: Trail::new(trail_length),
+            show_path: settings.get_bool("path.preview", false),
+            path: None,
+        }
+    }
+
+    pub fn set_path(&mut self, path: &CameraPath) {
+        let samples = ((path.duration() * PATH_SAMPLES_PER_SECOND) as usize).max(64);
+        let views: Vec<_> = (0..=samples).map(|i| path.sample(path.duration() * i as f32 / samples as f32)).collect();
+        self.path = Some(PathPreview {
+            eyes: views.iter().map(|view| view.eye).collect(),
+            targets: views.iter().map(|view| view.center).collect(),
+            keyframes: path.keyframes().iter().map(|key| (key.eye, key.target)).collect(),
+        });
+    }
+
+    pub fn toggle_body(&mut self, index: usize) {
+        if let Some(visible) = self.visible.get_mut(index) {
+            *visible = !*visible;
+        }
+    }
+
+    pub fn is_visible(&self, index: usize) -> bool {
+        self.visible.get(index).copied().unwrap_or(false)
+    }
+
+    // Se llama una vez por paso de simulación con las posiciones nuevas
+    pub fn record(&mut self, positions: &[DVec3], ship: DVec3) {
+        for (trail, position) in self.trails.iter_mut().zip(positions) {
+            trail.push(*position);
+        }
+        self.ship_trail.push(ship);
+    }
+
+    pub fn clear_trails(&mut self) {
+        for trail in &mut self.trails {
+            trail.clear();
+        }
+        self.ship_trail.clear();
+    }
+
+    // `centers` tiene, por cuerpo, la posición de aquel alrededor del cual orbita, y `days` fija los
+    // elementos de las órbitas. `view_projection` es la del espacio relativo al ojo `eye`, como la de la escena.
+    pub fn draw(&self, framebuffer: &mut Framebuffer, bodies: &[Cuerpo], centers: &[DVec3], days: f64, view_projection: &Mat4, eye: &DVec3) {
+        let projector = Projector::new(view_projection, eye, framebuffer.width as f32, framebuffer.height as f32, framebuffer.depth);
+
+        for (index, body) in bodies.iter().enumerate() {
+            if !self.is_visible(index) {
+                continue;
+            }
+            if self.show_orbits && body.orbit.is_visible() {
+                let style = LineStyle::solid(body.color, self.orbit_alpha);
+                let path: Vec<DVec3> = body.orbit.path(days, ORBIT_SEGMENTS).iter().map(|point| centers[index] + point).collect();
+                projector.draw(framebuffer, &path, |_| style);
+            }
+            if self.show_trails {
+                self.draw_trail(framebuffer, &projector, &self.trails[index], LineStyle::solid(body.color, self.trail_alpha));
+            }
+        }
+
+        if self.show_trails {
+            let style = LineStyle::dashed(Color::from_float(0.8, 0.9, 1.0), self.trail_alpha, 6.0, 4.0);
+            self.draw_trail(framebuffer, &projector, &self.ship_trail, style);
+        }
+
+        if let Some(path) = self.path.as_ref().filter(|_| self.show_path) {
+            self.draw_path(framebuffer, &projector, path);
+        }
+    }
+
+    // El ojo con línea continua, el objetivo punteado y una guía tenue de cada cuadro clave a su objetivo
+    fn draw_path(&self, framebuffer: &mut Framebuffer, projector: &Projector, path: &PathPreview) {
+        let color = Color::from_float(1.0, 0.45, 0.85);
+        let eye_style = LineStyle::solid(color, 0.9);
+        let target_style = LineStyle::dashed(color, 0.6, 4.0, 4.0);
+        let guide_style = LineStyle::dashed(color, 0.3, 2.0, 3.0);
+        projector.draw(framebuffer, &path.eyes, |_| eye_style);
+        projector.draw(framebuffer, &path.targets, |_| target_style);
+
+        for (eye, target) in &path.keyframes {
+            projector.draw(framebuffer, &[*eye, *target], |_| guide_style);
+            // Una cruz pequeña marca la posición del cuadro clave
+            let size = 0.08;
+            for axis in [DVec3::x(), DVec3::y(), DVec3::z()] {
+                projector.draw(framebuffer, &[eye - axis * size, eye + axis * size], |_| eye_style);
+            }
+        }
+    }
+
+    // La estela se desvanece hacia los puntos más antiguos
+    fn draw_trail(&self, framebuffer: &mut Framebuffer, projector: &Projector, trail: &Trail, style: LineStyle) {
+        let points: Vec<DVec3> = trail.points.iter().copied().collect();
+        let segments = points.len().max(2) - 1;
+        projector.draw(framebuffer, &points, |index| LineStyle {
+            alpha: style.alpha * (index + 1) as f32 / segments as f32,
+            ..style
+        });
+    }
+}
+
+// Lleva polilíneas del mundo a la pantalla, recortando los tramos que cruzan el plano cercano. Los
+// puntos se restan del ojo en f64 antes de pasar a f32, igual que la geometría de la escena.
+struct Projector {
+    view_projection: Mat4,
+    eye: DVec3,
+    width: f32,
+    height: f32,
+    depth: DepthRange, // La profundidad de las líneas se codifica como la del zbuffer
+}
+
+impl Projector {
+    fn new(view_projection: &Mat4, eye: &DVec3, width: f32, height: f32, depth: DepthRange) -> Self {
+        Projector { view_projection: *view_projection, eye: *eye, width, height, depth }
+    }
+
+    fn to_screen(&self, clip: &Vec4) -> Vec3 {
+        Vec3::new(
+            (clip.x / clip.w + 1.0) * 0.5 * self.width,
+            (1.0 - clip.y / clip.w) * 0.5 * self.height,
+            self.depth.stored(clip.w),
+        )
+    }
+
+    // `style` recibe el índice del tramo, para poder variar el color o la opacidad a lo largo de la línea
+    fn draw<F: Fn(usize) -> LineStyle>(&self, framebuffer: &mut Framebuffer, points: &[DVec3], style: F) {
+        let clip: Vec<Vec4> = points
+            .iter()
+            .map(|p| {
+                let p: Vec3 = (p - self.eye).cast();
+                self.view_projection * Vec4::new(p.x, p.y, p.z, 1.0)
+            })
+            .collect();
+
+        let mut phase = 0.0;
+        for (index, pair) in clip.windows(2).enumerate() {
+            let (mut a, mut b) = (pair[0], pair[1]);
+            let near = self.depth.near;
+            if a.w < near && b.w < near {
+                continue;
+            }
+            // Se recorta contra w = near, que en una proyección en perspectiva es el plano cercano
+            if a.w < near {
+                a += (b - a) * ((near - a.w) / (b.w - a.w));
+            } else if b.w < near {
+                b += (a - b) * ((near - b.w) / (a.w - b.w));
+            }
+            phase += framebuffer.line_from_phase(self.to_screen(&a), self.to_screen(&b), &style(index), phase);
+        }
+    }
+}
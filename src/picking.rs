@@ -0,0 +1,216 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::cuerpo::Cuerpo;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3, // Normalizada
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Ray { origin, direction: direction.normalize() }
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    // Distancia a la primera intersección con la esfera, o `None` si no la toca o queda detrás del origen
+    pub fn intersect_sphere(&self, center: Vec3, radius: f32) -> Option<f32> {
+        let to_center = center - self.origin;
+        let projection = to_center.dot(&self.direction);
+        let discriminant = projection * projection - (to_center.magnitude_squared() - radius * radius);
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        [projection - root, projection + root].into_iter().find(|t| *t >= 0.0)
+    }
+
+    // Möller–Trumbore; se aceptan ambas caras
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = self.direction.cross(&edge2);
+        let determinant = edge1.dot(&p);
+        if determinant.abs() < 1e-9 {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let offset = self.origin - a;
+        let u = offset.dot(&p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = offset.cross(&edge1);
+        let v = self.direction.dot(&q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(&q) * inverse;
+        (t >= 0.0).then_some(t)
+    }
+
+    // Lleva el rayo al espacio del modelo; la distancia que devuelvan las intersecciones ya no es la del mundo
+    pub fn transform(&self, matrix: &Mat4) -> Ray {
+        let origin = matrix * Vec4::new(self.origin.x, self.origin.y, self.origin.z, 1.0);
+        let direction = matrix * Vec4::new(self.direction.x, self.direction.y, self.direction.z, 0.0);
+        Ray::new(origin.xyz() / origin.w, direction.xyz())
+    }
+}
+
+// Distancia a la que el rayo toca la malla del cuerpo. Primero se prueba la esfera que la envuelve y
+// solo si acierta se recorren los triángulos en espacio del modelo.
+pub fn intersect_body(ray: &Ray, body: &Cuerpo, model_matrix: &Mat4) -> Option<f32> {
+    let center = model_matrix.column(3).xyz();
+    ray.intersect_sphere(center, body.bounding_radius())?;
+
+    let inverse = model_matrix.try_inverse()?;
+    let local = ray.transform(&inverse);
+    let hit = body
+        .vertex_array
+        .chunks_exact(3)
+        .filter_map(|tri| local.intersect_triangle(tri[0].position, tri[1].position, tri[2].position))
+        .min_by(|a, b| a.total_cmp(b))?;
+
+    // De vuelta al mundo para comparar con otros cuerpos
+    let local_hit = local.at(hit);
+    let point = model_matrix * Vec4::new(local_hit.x, local_hit.y, local_hit.z, 1.0);
+    Some((point.xyz() - ray.origin).magnitude())
+}
+
+// El índice del candidato más cercano que toca el rayo. Si ninguno lo toca, se acepta el que pase a
+// menos de `tolerance` radianes del rayo, para poder elegir cuerpos de pocos pixeles.
+pub fn pick(ray: &Ray, candidates: &[(usize, &Cuerpo, Mat4)], tolerance: f32) -> Option<usize> {
+    let hit = candidates
+        .iter()
+        .filter_map(|(index, body, model_matrix)| intersect_body(ray, body, model_matrix).map(|t| (*index, t)))
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+    if let Some((index, _)) = hit {
+        return Some(index);
+    }
+
+    candidates
+        .iter()
+        .filter_map(|(index, _, model_matrix)| {
+            let offset = model_matrix.column(3).xyz() - ray.origin;
+            let angle = ray.direction.dot(&offset.normalize()).clamp(-1.0, 1.0).acos();
+            (angle <= tolerance).then_some((*index, angle))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::Vec2;
+    use super::*;
+    use crate::color::Color;
+    use crate::cuerpo::Orbit;
+    use crate::material::Material;
+    use crate::vertex::Vertex;
+
+    fn assert_hit(hit: Option<f32>, expected: f32) {
+        let t = hit.expect("el rayo debería tocar");
+        assert!((t - expected).abs() < 1e-4, "{t} en vez de {expected}");
+    }
+
+    // Octaedro de radio 1: sus vértices están sobre los ejes, así que se sabe dónde lo toca cada rayo
+    fn octahedron(scale: f32) -> Cuerpo {
+        let axes = [Vec3::x(), Vec3::y(), Vec3::z(), -Vec3::x(), -Vec3::y(), -Vec3::z()];
+        let mut vertex_array = Vec::new();
+        for (a, b) in [(0, 1), (1, 3), (3, 4), (4, 0)] {
+            for pole in [2, 5] {
+                for index in [a, b, pole] {
+                    vertex_array.push(Vertex::new(axes[index], axes[index], Vec2::zeros()));
+                }
+            }
+        }
+        Cuerpo {
+            name: "octaedro".to_string(),
+            scale,
+            vertex_array,
+            orbit: Orbit::Circular { radius: 0.0, phase: 0.0, speed: 0.0 },
+            parent: None,
+            pole: Vec3::y(),
+            rotation_speed: 0.0,
+            shader: 0,
+            material: Material::new(0.0, 0.0),
+            atmosphere: None,
+            ring: None,
+            color: Color::black(),
+            disappearance_buffer: 0.0,
+        }
+    }
+
+    fn placed(body: &Cuerpo, center: Vec3) -> Mat4 {
+        Mat4::new_translation(&center) * Mat4::new_scaling(body.scale)
+    }
+
+    #[test]
+    fn sphere_hits_and_misses() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 2.0));
+        assert_hit(ray.intersect_sphere(Vec3::zeros(), 1.0), 4.0);
+        assert_eq!(ray.intersect_sphere(Vec3::new(2.0, 0.0, 0.0), 1.0), None);
+        // Rozando el borde
+        assert_hit(ray.intersect_sphere(Vec3::new(1.0, 0.0, 0.0), 1.0), 5.0);
+    }
+
+    #[test]
+    fn sphere_from_inside_and_behind() {
+        let ray = Ray::new(Vec3::zeros(), Vec3::z());
+        // Desde adentro cuenta la salida
+        assert_hit(ray.intersect_sphere(Vec3::new(0.0, 0.0, 0.5), 1.0), 1.5);
+        assert_eq!(ray.intersect_sphere(Vec3::new(0.0, 0.0, -5.0), 1.0), None);
+    }
+
+    #[test]
+    fn triangle_hits_both_faces_and_misses() {
+        let (a, b, c) = (Vec3::new(-1.0, -1.0, 0.0), Vec3::new(1.0, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_hit(Ray::new(Vec3::new(0.0, 0.0, -3.0), Vec3::z()).intersect_triangle(a, b, c), 3.0);
+        assert_hit(Ray::new(Vec3::new(0.0, 0.0, 3.0), -Vec3::z()).intersect_triangle(a, b, c), 3.0);
+        assert_eq!(Ray::new(Vec3::new(2.0, 0.0, -3.0), Vec3::z()).intersect_triangle(a, b, c), None);
+        // Detrás del origen y paralelo al plano
+        assert_eq!(Ray::new(Vec3::new(0.0, 0.0, 3.0), Vec3::z()).intersect_triangle(a, b, c), None);
+        assert_eq!(Ray::new(Vec3::new(0.0, 0.0, -3.0), Vec3::x()).intersect_triangle(a, b, c), None);
+    }
+
+    #[test]
+    fn body_distance_is_measured_in_the_world() {
+        let body = octahedron(2.0);
+        let ray = Ray::new(Vec3::zeros(), Vec3::z());
+        // El vértice -z del octaedro escalado queda en z = 10 - 2
+        assert_hit(intersect_body(&ray, &body, &placed(&body, Vec3::new(0.0, 0.0, 10.0))), 8.0);
+        assert_eq!(intersect_body(&ray, &body, &placed(&body, Vec3::new(0.0, 0.0, -10.0))), None);
+    }
+
+    #[test]
+    fn picks_the_nearest_body() {
+        let (near, far) = (octahedron(1.0), octahedron(3.0));
+        let ray = Ray::new(Vec3::zeros(), Vec3::z());
+        let candidates = [
+            (0, &far, placed(&far, Vec3::new(0.0, 0.0, 20.0))),
+            (1, &near, placed(&near, Vec3::new(0.0, 0.0, 10.0))),
+        ];
+        assert_eq!(pick(&ray, &candidates, 0.0), Some(1));
+        // Un cuerpo detrás de la cámara no se elige aunque esté en la misma recta
+        let candidates = [(0, &near, placed(&near, Vec3::new(0.0, 0.0, -10.0)))];
+        assert_eq!(pick(&ray, &candidates, 0.1), None);
+    }
+
+    #[test]
+    fn small_bodies_are_picked_within_the_tolerance() {
+        let (right, above) = (octahedron(0.01), octahedron(0.01));
+        let ray = Ray::new(Vec3::zeros(), Vec3::z());
+        // A 0.04 y 0.02 radianes del rayo, sin que lo toquen
+        let candidates = [
+            (0, &right, placed(&right, Vec3::new(0.4, 0.0, 10.0))),
+            (1, &above, placed(&above, Vec3::new(0.0, 0.2, 10.0))),
+        ];
+        assert_eq!(pick(&ray, &candidates, 0.01), None);
+        assert_eq!(pick(&ray, &candidates, 0.03), Some(1));
+        assert_eq!(pick(&ray, &candidates, 0.05), Some(1));
+        assert_eq!(pick(&ray, &candidates[..1], 0.05), Some(0));
+    }
+}
@@ -0,0 +1,494 @@
+use std::fs;
+use crate::color::{Color, encode_srgb, decode_srgb};
+use crate::framebuffer::Framebuffer;
+use crate::settings::Settings;
+
+// Efecto de pantalla que trabaja sobre el cuadro ya mapeado a [0, 1] (espacio lineal) y su profundidad
+pub trait PostEffect {
+    fn name(&self) -> &'static str;
+    // `frame` cuenta los cuadros dibujados, para los efectos que cambian en cada uno
+    fn apply(&self, framebuffer: &mut Framebuffer, frame: u32);
+}
+
+pub struct PostPass {
+    pub enabled: bool,
+    pub effect: Box<dyn PostEffect>,
+}
+
+// Lista ordenada de pases; cada uno se puede activar o desactivar en tiempo de ejecución
+pub struct PostStack {
+    pub passes: Vec<PostPass>,
+}
+
+// Orden por defecto: primero lo que limpia la imagen, al final lo que simula el lente y la pantalla
+const DEFAULT_ORDER: [&str; 7] = [
+    "fxaa",
+    "depth_of_field",
+    "chromatic_aberration",
+    "color_grading",
+    "vignette",
+    "film_grain",
+    "crt",
+];
+
+impl PostStack {
+    pub fn new() -> Self {
+        PostStack { passes: Vec::new() }
+    }
+
+    pub fn push(&mut self, effect: Box<dyn PostEffect>, enabled: bool) {
+        self.passes.push(PostPass { enabled, effect });
+    }
+
+    // Arma la cadena desde la sección [postprocess] de la configuración:
+    //   order = fxaa, vignette, ...       (orden y efectos disponibles)
+    //   vignette.enabled = true           (estado inicial de cada efecto)
+    //   vignette.strength = 0.5           (parámetros propios de cada efecto)
+    pub fn from_settings(settings: &Settings) -> Self {
+        let order = settings
+            .get_list("postprocess.order")
+            .unwrap_or_else(|| DEFAULT_ORDER.iter().map(|name| name.to_string()).collect());
+
+        let mut stack = PostStack::new();
+        for name in order {
+            let key = |parameter: &str| format!("postprocess.{}.{}", name, parameter);
+            let effect: Box<dyn PostEffect> = match name.as_str() {
+                "fxaa" => Box::new(Fxaa),
+                "depth_of_field" => Box::new(DepthOfField {
+                    focus_range: settings.get_f32(&key("focus_range"), 0.3),
+                    max_radius: settings.get_f32(&key("max_radius"), 6.0),
+                }),
+                "chromatic_aberration" => Box::new(ChromaticAberration {
+                    strength: settings.get_f32(&key("strength"), 3.0),
+                }),
+                "color_grading" => Box::new(ColorGrading::new(
+                    settings.get_f32(&key("contrast"), 1.1),
+                    settings.get_f32(&key("saturation"), 1.15),
+                    settings.get_f32(&key("temperature"), 0.0),
+                    settings.get(&key("lut")),
+                )),
+                "vignette" => Box::new(Vignette {
+                    strength: settings.get_f32(&key("strength"), 0.45),
+                    radius: settings.get_f32(&key("radius"), 0.75),
+                }),
+                "film_grain" => Box::new(FilmGrain {
+                    strength: settings.get_f32(&key("strength"), 0.05),
+                }),
+                "crt" => Box::new(Crt {
+                    scanline_strength: settings.get_f32(&key("scanline_strength"), 0.35),
+                    curvature: settings.get_f32(&key("curvature"), 0.08),
+                }),
+                _ => {
+                    eprintln!("Efecto de postproceso desconocido: {}", name);
+                    continue;
+                }
+            };
+            stack.push(effect, settings.get_bool(&key("enabled"), false));
+        }
+        stack
+    }
+
+    // Devuelve el estado nuevo del efecto para mostrarlo en el HUD
+    pub fn toggle(&mut self, index: usize) -> Option<String> {
+        let pass = self.passes.get_mut(index)?;
+        pass.enabled = !pass.enabled;
+        Some(format!(
+            "{}: {}",
+            pass.effect.name(),
+            if pass.enabled { "activado" } else { "desactivado" }
+        ))
+    }
+
+    pub fn apply(&self, framebuffer: &mut Framebuffer, frame: u32) {
+        for pass in self.passes.iter().filter(|pass| pass.enabled) {
+            pass.effect.apply(framebuffer, frame);
+        }
+    }
+}
+
+// Muestreo bilineal con coordenadas de pixel (los centros están en +0.5)
+fn sample(pixels: &[Color], width: usize, height: usize, x: f32, y: f32) -> Color {
+    let fx = (x - 0.5).clamp(0.0, (width - 1) as f32);
+    let fy = (y - 0.5).clamp(0.0, (height - 1) as f32);
+    let x0 = fx as usize;
+    let y0 = fy as usize;
+    let x1 = (x0 + 1).min(width - 1);
+    let y1 = (y0 + 1).min(height - 1);
+    let tx = fx - x0 as f32;
+    let ty = fy - y0 as f32;
+
+    let top = pixels[y0 * width + x0].lerp(&pixels[y0 * width + x1], tx);
+    let bottom = pixels[y1 * width + x0].lerp(&pixels[y1 * width + x1], tx);
+    top.lerp(&bottom, ty)
+}
+
+// Luma perceptual aproximada (el FXAA original trabaja con valores con gamma)
+fn luma(color: &Color) -> f32 {
+    color.luminance().sqrt()
+}
+
+// FXAA de una sola pasada: busca la dirección del borde y promedia a lo largo de ella
+pub struct Fxaa;
+
+impl PostEffect for Fxaa {
+    fn name(&self) -> &'static str {
+        "fxaa"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer, _frame: u32) {
+        const REDUCE_MIN: f32 = 1.0 / 128.0;
+        const REDUCE_MUL: f32 = 1.0 / 8.0;
+        const SPAN_MAX: f32 = 8.0;
+
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let source = framebuffer.color.clone();
+        let at = |x: usize, y: usize| luma(&source[y.min(height - 1) * width + x.min(width - 1)]);
+
+        for y in 0..height {
+            for x in 0..width {
+                let luma_nw = at(x.saturating_sub(1), y.saturating_sub(1));
+                let luma_ne = at(x + 1, y.saturating_sub(1));
+                let luma_sw = at(x.saturating_sub(1), y + 1);
+                let luma_se = at(x + 1, y + 1);
+                let luma_m = at(x, y);
+
+                let luma_min = luma_m.min(luma_nw.min(luma_ne).min(luma_sw.min(luma_se)));
+                let luma_max = luma_m.max(luma_nw.max(luma_ne).max(luma_sw.max(luma_se)));
+
+                let mut dir_x = -((luma_nw + luma_ne) - (luma_sw + luma_se));
+                let mut dir_y = (luma_nw + luma_sw) - (luma_ne + luma_se);
+                if dir_x.abs() + dir_y.abs() < 1e-5 {
+                    continue;
+                }
+
+                let dir_reduce = ((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL).max(REDUCE_MIN);
+                let inverse_min = 1.0 / (dir_x.abs().min(dir_y.abs()) + dir_reduce);
+                dir_x = (dir_x * inverse_min).clamp(-SPAN_MAX, SPAN_MAX);
+                dir_y = (dir_y * inverse_min).clamp(-SPAN_MAX, SPAN_MAX);
+
+                let cx = x as f32 + 0.5;
+                let cy = y as f32 + 0.5;
+                let tap = |t: f32| sample(&source, width, height, cx + dir_x * t, cy + dir_y * t);
+
+                let color_a = (tap(1.0 / 3.0 - 0.5) + tap(2.0 / 3.0 - 0.5)) * 0.5;
+                let color_b = color_a * 0.5 + (tap(-0.5) + tap(0.5)) * 0.25;
+                let luma_b = luma(&color_b);
+
+                framebuffer.color[y * width + x] = if luma_b < luma_min || luma_b > luma_max {
+                    color_a
+                } else {
+                    color_b
+                };
+            }
+        }
+    }
+}
+
+// Profundidad de campo con enfoque automático en el centro de la pantalla
+pub struct DepthOfField {
+    pub focus_range: f32, // Fracción de la distancia de enfoque que sigue nítida
+    pub max_radius: f32,  // Radio máximo del círculo de confusión, en pixeles
+}
+
+impl PostEffect for DepthOfField {
+    fn name(&self) -> &'static str {
+        "depth_of_field"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer, _frame: u32) {
+        // Disco de muestras en espiral (ángulo dorado)
+        const TAPS: usize = 12;
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let source = framebuffer.color.clone();
+
+        // Distancia a la cámara a partir del zbuffer, con los planos de la cámara; el fondo queda en el plano lejano
+        let depth_range = framebuffer.depth;
+        let (near, far) = (depth_range.near, depth_range.far);
+        let distance_at = |index: usize| {
+            let depth = framebuffer.zbuffer[index];
+            if depth.is_finite() {
+                depth_range.distance(depth).clamp(near, far)
+            } else {
+                far
+            }
+        };
+        let focus = distance_at((height / 2) * width + width / 2);
+
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                let defocus = (distance_at(index) - focus).abs() / focus;
+                let blur = (defocus / self.focus_range.max(1e-4) - 1.0).clamp(0.0, 1.0);
+                let radius = blur * self.max_radius;
+                if radius < 0.5 {
+                    continue;
+                }
+
+                let mut sum = source[index];
+                for tap in 1..=TAPS {
+                    let angle = tap as f32 * 2.399_963;
+                    let distance = radius * (tap as f32 / TAPS as f32).sqrt();
+                    let sx = x as f32 + 0.5 + angle.cos() * distance;
+                    let sy = y as f32 + 0.5 + angle.sin() * distance;
+                    sum = sum + sample(&source, width, height, sx, sy);
+                }
+                framebuffer.color[index] = sum * (1.0 / (TAPS + 1) as f32);
+            }
+        }
+    }
+}
+
+// Separa los canales rojo y azul hacia los bordes, como un lente barato
+pub struct ChromaticAberration {
+    pub strength: f32, // Desplazamiento en pixeles en las esquinas
+}
+
+impl PostEffect for ChromaticAberration {
+    fn name(&self) -> &'static str {
+        "chromatic_aberration"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer, _frame: u32) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let source = framebuffer.color.clone();
+        let cx = width as f32 * 0.5;
+        let cy = height as f32 * 0.5;
+
+        for y in 0..height {
+            for x in 0..width {
+                let px = x as f32 + 0.5;
+                let py = y as f32 + 0.5;
+                // Desplazamiento radial normalizado: cero en el centro, `strength` en las esquinas
+                let dx = (px - cx) / cx * self.strength;
+                let dy = (py - cy) / cy * self.strength;
+
+                let red = sample(&source, width, height, px + dx, py + dy).r;
+                let blue = sample(&source, width, height, px - dx, py - dy).b;
+                let pixel = &mut framebuffer.color[y * width + x];
+                pixel.r = red;
+                pixel.b = blue;
+            }
+        }
+    }
+}
+
+// Contraste, saturación y temperatura, y opcionalmente una LUT 3D en formato .cube
+pub struct ColorGrading {
+    pub contrast: f32,
+    pub saturation: f32,
+    pub temperature: f32, // Positivo = más cálido, negativo = más frío
+    lut: Option<Lut>,
+}
+
+impl ColorGrading {
+    pub fn new(contrast: f32, saturation: f32, temperature: f32, lut_path: Option<&str>) -> Self {
+        let lut = lut_path.and_then(|path| {
+            Lut::load(path)
+                .map_err(|error| eprintln!("No se pudo cargar la LUT: {}", error))
+                .ok()
+        });
+        ColorGrading { contrast, saturation, temperature, lut }
+    }
+}
+
+impl PostEffect for ColorGrading {
+    fn name(&self) -> &'static str {
+        "color_grading"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer, _frame: u32) {
+        for pixel in framebuffer.color.iter_mut() {
+            // Las curvas de color se ajustan sobre valores con gamma, como en cualquier editor de imagen
+            let mut r = encode_srgb(pixel.r);
+            let mut g = encode_srgb(pixel.g);
+            let mut b = encode_srgb(pixel.b);
+
+            r += self.temperature * 0.1;
+            b -= self.temperature * 0.1;
+
+            let gray = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+            r = gray + (r - gray) * self.saturation;
+            g = gray + (g - gray) * self.saturation;
+            b = gray + (b - gray) * self.saturation;
+
+            r = (r - 0.5) * self.contrast + 0.5;
+            g = (g - 0.5) * self.contrast + 0.5;
+            b = (b - 0.5) * self.contrast + 0.5;
+
+            if let Some(lut) = &self.lut {
+                let graded = lut.sample(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0));
+                r = graded.r;
+                g = graded.g;
+                b = graded.b;
+            }
+
+            *pixel = Color::from_float(decode_srgb(r), decode_srgb(g), decode_srgb(b));
+        }
+    }
+}
+
+// LUT 3D de Adobe .cube; las entradas y salidas están en valores con gamma
+struct Lut {
+    size: usize,
+    table: Vec<Color>,
+}
+
+impl Lut {
+    fn load(filename: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let mut size = 0;
+        let mut table = Vec::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("LUT_3D_SIZE") {
+                size = value.trim().parse().map_err(|_| format!("{}: LUT_3D_SIZE inválido", filename))?;
+                continue;
+            }
+            // TITLE, DOMAIN_MIN, DOMAIN_MAX y demás metadatos no se usan
+            if line.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                continue;
+            }
+            let values: Vec<f32> = line
+                .split_whitespace()
+                .map(|v| v.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("{}: línea inválida `{}`", filename, line))?;
+            if values.len() != 3 {
+                return Err(format!("{}: línea inválida `{}`", filename, line));
+            }
+            table.push(Color { r: values[0], g: values[1], b: values[2] });
+        }
+
+        if size < 2 || table.len() != size * size * size {
+            return Err(format!("{}: se esperaban {}^3 entradas", filename, size));
+        }
+        Ok(Lut { size, table })
+    }
+
+    // Interpolación trilineal; en .cube el rojo es el índice que cambia más rápido
+    fn sample(&self, r: f32, g: f32, b: f32) -> Color {
+        let scale = (self.size - 1) as f32;
+        let (fr, fg, fb) = (r * scale, g * scale, b * scale);
+        let (r0, g0, b0) = (fr as usize, fg as usize, fb as usize);
+        let (r1, g1, b1) = ((r0 + 1).min(self.size - 1), (g0 + 1).min(self.size - 1), (b0 + 1).min(self.size - 1));
+        let (tr, tg, tb) = (fr - r0 as f32, fg - g0 as f32, fb - b0 as f32);
+        let at = |r: usize, g: usize, b: usize| self.table[(b * self.size + g) * self.size + r];
+
+        let c00 = at(r0, g0, b0).lerp(&at(r1, g0, b0), tr);
+        let c10 = at(r0, g1, b0).lerp(&at(r1, g1, b0), tr);
+        let c01 = at(r0, g0, b1).lerp(&at(r1, g0, b1), tr);
+        let c11 = at(r0, g1, b1).lerp(&at(r1, g1, b1), tr);
+        c00.lerp(&c10, tg).lerp(&c01.lerp(&c11, tg), tb)
+    }
+}
+
+// Oscurece las esquinas
+pub struct Vignette {
+    pub strength: f32,
+    pub radius: f32, // Distancia al centro (1.0 = esquina) donde empieza a oscurecer
+}
+
+impl PostEffect for Vignette {
+    fn name(&self) -> &'static str {
+        "vignette"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer, _frame: u32) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let cx = width as f32 * 0.5;
+        let cy = height as f32 * 0.5;
+        let corner = (cx * cx + cy * cy).sqrt();
+
+        for y in 0..height {
+            for x in 0..width {
+                let dx = x as f32 + 0.5 - cx;
+                let dy = y as f32 + 0.5 - cy;
+                let distance = (dx * dx + dy * dy).sqrt() / corner;
+                let t = ((distance - self.radius) / (1.0 - self.radius).max(1e-4)).clamp(0.0, 1.0);
+                let falloff = 1.0 - self.strength * t * t * (3.0 - 2.0 * t);
+                let index = y * width + x;
+                framebuffer.color[index] = framebuffer.color[index] * falloff;
+            }
+        }
+    }
+}
+
+// Grano de película: ruido distinto en cada cuadro
+pub struct FilmGrain {
+    pub strength: f32,
+}
+
+impl PostEffect for FilmGrain {
+    fn name(&self) -> &'static str {
+        "film_grain"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer, frame: u32) {
+        let width = framebuffer.width;
+        for (index, pixel) in framebuffer.color.iter_mut().enumerate() {
+            let noise = hash((index % width) as u32, (index / width) as u32, frame) - 0.5;
+            // El grano se nota más en los medios tonos que en negros puros
+            let grain = noise * self.strength * (pixel.luminance().sqrt() + 0.1);
+            *pixel = Color::from_float(pixel.r + grain, pixel.g + grain, pixel.b + grain);
+        }
+    }
+}
+
+// Hash entero barato a [0, 1)
+fn hash(x: u32, y: u32, frame: u32) -> f32 {
+    let mut h = x.wrapping_mul(374761393) ^ y.wrapping_mul(668265263) ^ frame.wrapping_mul(2246822519);
+    h = (h ^ (h >> 13)).wrapping_mul(1274126177);
+    h ^= h >> 16;
+    (h & 0x00FF_FFFF) as f32 / 16_777_216.0
+}
+
+// Monitor CRT: pantalla curva, líneas de barrido y máscara de fósforo RGB
+pub struct Crt {
+    pub scanline_strength: f32,
+    pub curvature: f32,
+}
+
+impl PostEffect for Crt {
+    fn name(&self) -> &'static str {
+        "crt"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer, _frame: u32) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let source = framebuffer.color.clone();
+
+        for y in 0..height {
+            for x in 0..width {
+                // Distorsión de barril en coordenadas [-1, 1]
+                let u = (x as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+                let v = (y as f32 + 0.5) / height as f32 * 2.0 - 1.0;
+                let bend = 1.0 + self.curvature * (u * u + v * v);
+                let (su, sv) = (u * bend, v * bend);
+
+                let index = y * width + x;
+                if su.abs() > 1.0 || sv.abs() > 1.0 {
+                    framebuffer.color[index] = Color::black();
+                    continue;
+                }
+
+                let sx = (su + 1.0) * 0.5 * width as f32;
+                let sy = (sv + 1.0) * 0.5 * height as f32;
+                let mut color = sample(&source, width, height, sx, sy);
+
+                if y % 2 == 1 {
+                    color = color * (1.0 - self.scanline_strength);
+                }
+                let mask = match x % 3 {
+                    0 => Color::from_float(1.0, 0.8, 0.8),
+                    1 => Color::from_float(0.8, 1.0, 0.8),
+                    _ => Color::from_float(0.8, 0.8, 1.0),
+                };
+                framebuffer.color[index] = color * mask;
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use minifb::{InputCallback, Key, KeyRepeat, Window};
+use crate::calendar;
+
+// Caracteres que escribe el usuario. minifb los entrega por un callback ya traducidos según la
+// distribución del teclado, así que los dos puntos o el guion salen bien en cualquier idioma.
+struct Typed(Rc<RefCell<String>>);
+
+impl InputCallback for Typed {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(c) = char::from_u32(uni_char).filter(|c| c.is_ascii_graphic() || *c == ' ') {
+            self.0.borrow_mut().push(c);
+        }
+    }
+}
+
+// Línea para escribir la fecha a la que saltar (tecla Intro). Mientras está abierta, el teclado
+// escribe en ella y no controla la simulación.
+pub struct DatePrompt {
+    typed: Rc<RefCell<String>>,
+    text: Option<String>,
+    pub invalid: bool, // El último intento no se pudo leer como fecha
+}
+
+impl DatePrompt {
+    pub fn new(window: &mut Window) -> Self {
+        let typed = Rc::new(RefCell::new(String::new()));
+        window.set_input_callback(Box::new(Typed(typed.clone())));
+        DatePrompt { typed, text: None, invalid: false }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.text.is_some()
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    // Devuelve el día juliano elegido al confirmar con Intro; Esc cierra sin saltar
+    pub fn update(&mut self, window: &Window) -> Option<f64> {
+        // Lo que se escribió con la línea cerrada eran teclas de control
+        let typed = std::mem::take(&mut *self.typed.borrow_mut());
+        let Some(text) = &mut self.text else {
+            if window.is_key_pressed(Key::Enter, KeyRepeat::No) || window.is_key_pressed(Key::NumPadEnter, KeyRepeat::No) {
+                self.text = Some(String::new());
+                self.invalid = false;
+            }
+            return None;
+        };
+
+        text.push_str(&typed);
+        if window.is_key_pressed(Key::Backspace, KeyRepeat::Yes) {
+            text.pop();
+        }
+        if !typed.is_empty() {
+            self.invalid = false;
+        }
+        if window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+            self.text = None;
+            return None;
+        }
+        if window.is_key_pressed(Key::Enter, KeyRepeat::No) || window.is_key_pressed(Key::NumPadEnter, KeyRepeat::No) {
+            match calendar::parse_instant(text) {
+                Some(julian_day) => {
+                    self.text = None;
+                    return Some(julian_day);
+                }
+                None => self.invalid = true,
+            }
+        }
+        None
+    }
+}
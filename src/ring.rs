@@ -0,0 +1,135 @@
+use nalgebra_glm::{Vec2, Vec3, Mat4};
+use std::fs;
+use crate::color::Color;
+use crate::vertex::Vertex;
+
+const RING_SEGMENTS: usize = 128; // Divisiones alrededor del planeta
+const RING_BANDS: usize = 4;      // Divisiones radiales, para que la interpolación no deforme el perfil
+
+pub enum RingProfile {
+    // Bandas generadas con senos y una división al estilo de Cassini
+    Procedural { inner_color: Color, outer_color: Color },
+    // Textura 1D de adentro hacia afuera: color y opacidad por muestra
+    Texture(Vec<(Color, f32)>),
+}
+
+pub struct Ring {
+    pub inner_radius: f32, // En radios del planeta
+    pub outer_radius: f32,
+    pub tilt: f32,         // Inclinación del plano de los anillos (radianes, sobre el eje X)
+    pub equator: Mat4,     // Orientación del planeta; la inclinación se mide desde su ecuador
+    pub opacity: f32,      // Opacidad máxima de las partes más densas
+    pub profile: RingProfile,
+    pub vertex_array: Vec<Vertex>,
+}
+
+impl Ring {
+    pub fn new(inner_radius: f32, outer_radius: f32, tilt: f32, opacity: f32, profile: RingProfile) -> Self {
+        Ring {
+            inner_radius,
+            outer_radius,
+            tilt,
+            equator: Mat4::identity(),
+            opacity,
+            profile,
+            vertex_array: annulus(inner_radius, outer_radius),
+        }
+    }
+
+    // Carga una textura 1D en texto plano: una muestra por línea con "r g b a" (0-255)
+    pub fn load_profile(filename: &str) -> Result<RingProfile, String> {
+        let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let mut samples = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values: Vec<u8> = line
+                .split_whitespace()
+                .map(|v| v.parse::<u8>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("{}:{}: {}", filename, number + 1, e))?;
+            if values.len() != 4 {
+                return Err(format!("{}:{}: se esperaban 4 valores", filename, number + 1));
+            }
+            samples.push((Color::new(values[0], values[1], values[2]), values[3] as f32 / 255.0));
+        }
+        if samples.is_empty() {
+            return Err(format!("{}: el perfil está vacío", filename));
+        }
+        Ok(RingProfile::Texture(samples))
+    }
+
+    // Rotación que lleva el plano XZ del anillo a su inclinación
+    pub fn tilt_matrix(&self) -> Mat4 {
+        self.equator * Mat4::from_axis_angle(&Vec3::x_axis(), self.tilt)
+    }
+
+    // Normal del plano de los anillos en espacio de mundo
+    pub fn normal(&self) -> Vec3 {
+        (self.tilt_matrix() * Vec3::y().push(0.0)).xyz()
+    }
+
+    // Color y opacidad a una distancia `r` del centro, en radios del planeta
+    pub fn sample(&self, r: f32) -> (Color, f32) {
+        if r < self.inner_radius || r > self.outer_radius {
+            return (Color::black(), 0.0);
+        }
+        let t = (r - self.inner_radius) / (self.outer_radius - self.inner_radius);
+
+        match &self.profile {
+            RingProfile::Procedural { inner_color, outer_color } => {
+                // Bandas finas sobre una densidad que crece hacia el anillo principal
+                let bands = 0.65 + 0.2 * (t * 90.0).sin() + 0.15 * (t * 37.0).sin();
+                let base = if t < 0.25 { 0.35 } else { 1.0 };
+                // División oscura entre los dos anillos principales
+                let gap = ((t - 0.68).abs() / 0.03).min(1.0);
+                // Los bordes se desvanecen suavemente
+                let edges = (t / 0.05).min(1.0) * ((1.0 - t) / 0.05).min(1.0);
+
+                let density = (bands * base * gap * edges).clamp(0.0, 1.0);
+                let color = inner_color.lerp(outer_color, t) * (0.8 + 0.2 * bands);
+                (color, density * self.opacity)
+            }
+            RingProfile::Texture(samples) => {
+                let position = t * (samples.len() - 1) as f32;
+                let i = (position.floor() as usize).min(samples.len() - 1);
+                let j = (i + 1).min(samples.len() - 1);
+                let f = position - i as f32;
+                let (color_a, alpha_a) = samples[i];
+                let (color_b, alpha_b) = samples[j];
+                (color_a.lerp(&color_b, f), (alpha_a + (alpha_b - alpha_a) * f) * self.opacity)
+            }
+        }
+    }
+}
+
+// Corona plana en el plano XZ, con radios en unidades del planeta
+fn annulus(inner_radius: f32, outer_radius: f32) -> Vec<Vertex> {
+    let normal = Vec3::new(0.0, 1.0, 0.0);
+    let mut vertices = Vec::with_capacity(RING_SEGMENTS * RING_BANDS * 6);
+
+    let point = |segment: usize, band: usize| {
+        let angle = segment as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+        let t = band as f32 / RING_BANDS as f32;
+        let radius = inner_radius + (outer_radius - inner_radius) * t;
+        Vertex::new(
+            Vec3::new(radius * angle.cos(), 0.0, radius * angle.sin()),
+            normal,
+            Vec2::new(t, segment as f32 / RING_SEGMENTS as f32),
+        )
+    };
+
+    for segment in 0..RING_SEGMENTS {
+        for band in 0..RING_BANDS {
+            let a = point(segment, band);
+            let b = point(segment + 1, band);
+            let c = point(segment + 1, band + 1);
+            let d = point(segment, band + 1);
+            vertices.extend([a.clone(), b, c.clone(), a, c, d]);
+        }
+    }
+
+    vertices
+}
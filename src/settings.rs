@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use nalgebra_glm::{DVec3, Vec3};
+
+// Configuración en formato INI sencillo: `clave = valor`, secciones `[nombre]` y comentarios con `#`.
+// Las claves de una sección se guardan como `seccion.clave`.
+pub struct Settings {
+    values: HashMap<String, String>,
+}
+
+impl Settings {
+    pub fn empty() -> Self {
+        Settings { values: HashMap::new() }
+    }
+
+    pub fn load(filename: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        Settings::parse(&contents).map_err(|e| format!("{}:{}", filename, e))
+    }
+
+    // Un archivo ausente no es un error: simplemente se usan los valores por defecto
+    pub fn load_or_default(filename: &str) -> Self {
+        if !Path::new(filename).exists() {
+            return Settings::empty();
+        }
+        Settings::load(filename).unwrap_or_else(|error| {
+            eprintln!("No se pudo leer la configuración: {}", error);
+            Settings::empty()
+        })
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut section = String::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len() - 1].trim().to_string();
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{}: se esperaba `clave = valor`", number + 1))?;
+            let key = if section.is_empty() {
+                key.trim().to_string()
+            } else {
+                format!("{}.{}", section, key.trim())
+            };
+            values.insert(key, value.trim().to_string());
+        }
+
+        Ok(Settings { values })
+    }
+
+    // Los argumentos de línea de comandos tienen prioridad sobre el archivo:
+    //   --clave valor, --clave=valor o --bandera (equivale a `bandera = true`)
+    pub fn apply_args<I: IntoIterator<Item = String>>(&mut self, args: I) -> Result<(), String> {
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let key = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("argumento inesperado: {}", arg))?;
+            if let Some((key, value)) = key.split_once('=') {
+                self.set(key, value);
+            } else if args.peek().is_some_and(|next| !next.starts_with("--")) {
+                let value = args.next().unwrap();
+                self.set(key, &value);
+            } else {
+                self.set(key, "true");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|v| v.as_str())
+    }
+
+    pub fn get_f32(&self, key: &str, default: f32) -> f32 {
+        match self.get(key) {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                eprintln!("Valor inválido para {}: {}", key, value);
+                default
+            }),
+            None => default,
+        }
+    }
+
+    // Para valores que no caben en f32 sin perder precisión, como los días julianos
+    pub fn get_f64(&self, key: &str, default: f64) -> f64 {
+        match self.get(key) {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                eprintln!("Valor inválido para {}: {}", key, value);
+                default
+            }),
+            None => default,
+        }
+    }
+
+    pub fn get_usize(&self, key: &str, default: usize) -> usize {
+        match self.get(key) {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                eprintln!("Valor inválido para {}: {}", key, value);
+                default
+            }),
+            None => default,
+        }
+    }
+
+    pub fn get_bool(&self, key: &str, default: bool) -> bool {
+        match self.get(key) {
+            Some("true") | Some("1") | Some("on") | Some("yes") => true,
+            Some("false") | Some("0") | Some("off") | Some("no") => false,
+            Some(value) => {
+                eprintln!("Valor inválido para {}: {}", key, value);
+                default
+            }
+            None => default,
+        }
+    }
+
+    // Vector como `x, y, z`
+    pub fn get_vec3(&self, key: &str) -> Option<Vec3> {
+        self.get_dvec3(key).map(|vector| vector.cast())
+    }
+
+    // Lo mismo en f64, para posiciones en el mundo
+    pub fn get_dvec3(&self, key: &str) -> Option<DVec3> {
+        let value = self.get(key)?;
+        let vector = parse_dvec3(value);
+        if vector.is_none() {
+            eprintln!("Valor inválido para {}: se esperaban tres números", key);
+        }
+        vector
+    }
+
+    // Tamaño en pixeles como `ancho x alto`, por ejemplo `1280x720`
+    pub fn get_size(&self, key: &str) -> Option<(usize, usize)> {
+        let value = self.get(key)?;
+        let size = value
+            .split_once(['x', 'X'])
+            .and_then(|(width, height)| Some((width.trim().parse().ok()?, height.trim().parse().ok()?)))
+            .filter(|(width, height)| *width > 0 && *height > 0);
+        if size.is_none() {
+            eprintln!("Valor inválido para {}: se esperaba ancho x alto", key);
+        }
+        size
+    }
+
+    // Lista separada por comas
+    pub fn get_list(&self, key: &str) -> Option<Vec<String>> {
+        self.get(key).map(|value| {
+            value
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+    }
+}
+
+// `x, y, z`. Las tres componentes tienen que ser números: si una está mal escrita se rechaza el vector
+// entero en lugar de correr las demás.
+pub fn parse_dvec3(text: &str) -> Option<DVec3> {
+    let values = text.split(',').map(|v| v.trim().parse::<f64>()).collect::<Result<Vec<_>, _>>().ok()?;
+    match values.as_slice() {
+        &[x, y, z] => Some(DVec3::new(x, y, z)),
+        _ => None,
+    }
+}
@@ -1,10 +1,9 @@
-use fastnoise_lite::FastNoiseLite;
-use nalgebra_glm::{Vec3, Vec4, Mat3, dot, mat4_to_mat3};
+use nalgebra_glm::{Vec3, Vec4, Mat3, mat4_to_mat3};
+use nalgebra::Point3;
 use crate::vertex::Vertex;
 use crate::Uniforms;
 use crate::fragment::Fragment;
 use crate::color::Color;
-use std::f32::consts::PI;
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
   // Transform position
@@ -16,12 +15,12 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
   );
   let transformed = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
 
-  // Perform perspective division
+  // Perform perspective division; la profundidad sale de w según el modo del zbuffer
   let w = transformed.w;
   let ndc_position = Vec4::new(
     transformed.x / w,
     transformed.y / w,
-    transformed.z / w,
+    uniforms.depth.vertex_depth(w),
     1.0
   );
 
@@ -45,8 +44,8 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
   }
 }
 
-pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, index: usize) -> Color {
-  match index {
+pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, shader: usize) -> Color {
+  match shader {
       0 => sun_shader(fragment, uniforms),    
       1 => lava_planet_shader(fragment, uniforms), 
       2 => planet1_shader(fragment, uniforms),  
@@ -59,6 +58,137 @@ pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, index: usize) -
   }
 }
 
+// La iluminación de los shaders se ajustó sobre valores sRGB; elevar el factor a 2.2
+// conserva ese mismo aspecto ahora que los colores se mezclan en espacio lineal
+fn shade(color: Color, intensity: f32) -> Color {
+  color * intensity.powf(2.2)
+}
+
+// Posición del fragmento en espacio de mundo
+fn world_position(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+  uniforms.model_matrix.transform_point(&Point3::from(fragment.vertex_position)).coords
+}
+
+// Luz difusa del sol con la normal interpolada, sin relieve ni sombras; la usan las vistas de depuración.
+// El propio sol contiene la luz y se ve entero iluminado.
+pub fn sun_intensity(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+  let (center, radius) = body_sphere(uniforms);
+  if (uniforms.sun_position - center).magnitude() <= radius {
+    return 1.0;
+  }
+  let light_dir = (uniforms.sun_position - world_position(fragment, uniforms)).normalize();
+  fragment.normal.dot(&light_dir).max(0.0)
+}
+
+// Centro y radio en espacio de mundo del cuerpo que se está dibujando
+fn body_sphere(uniforms: &Uniforms) -> (Vec3, f32) {
+  let center = uniforms.model_matrix.transform_point(&Point3::origin()).coords;
+  let radius = (uniforms.model_matrix * Vec4::new(1.0, 0.0, 0.0, 0.0)).xyz().magnitude();
+  (center, radius)
+}
+
+// Intensidad de la luz del sol usando la normal perturbada por el gradiente de un campo de alturas.
+// `height` recibe la posición en espacio de objeto y el gradiente se aproxima con diferencias finitas.
+fn surface_intensity<F: Fn(Vec3) -> f32>(fragment: &Fragment, uniforms: &Uniforms, height: F) -> f32 {
+  let position = world_position(fragment, uniforms);
+  let light_dir = (uniforms.sun_position - position).normalize();
+  let shadow = ring_transmittance(&position, &light_dir, uniforms);
+
+  let strength = uniforms.material.bump_strength;
+  if strength <= 0.0 {
+    return fragment.normal.dot(&light_dir).max(0.0) * shadow;
+  }
+
+  let p = fragment.vertex_position;
+  let eps = 0.002;
+  let h = height(p);
+  let gradient = Vec3::new(
+    height(p + Vec3::new(eps, 0.0, 0.0)) - h,
+    height(p + Vec3::new(0.0, eps, 0.0)) - h,
+    height(p + Vec3::new(0.0, 0.0, eps)) - h,
+  ) / eps;
+
+  // Llevamos la normal interpolada de vuelta a espacio de objeto, donde vive el gradiente
+  let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
+  let normal = (model_mat3.transpose() * fragment.normal).normalize();
+
+  // Solo la parte tangente del gradiente inclina la normal
+  let tangent_gradient = gradient - normal * gradient.dot(&normal);
+  let bumped_normal = (normal - tangent_gradient * strength).normalize();
+
+  // Regresamos a espacio de mundo con la misma matriz de normales del vertex shader
+  let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
+  let world_normal = (normal_matrix * bumped_normal).normalize();
+
+  world_normal.dot(&light_dir).max(0.0) * shadow
+}
+
+// Fracción de la luz que atraviesa los anillos del propio planeta antes de llegar a `position`
+fn ring_transmittance(position: &Vec3, light_dir: &Vec3, uniforms: &Uniforms) -> f32 {
+  let ring = match uniforms.ring {
+    Some(ring) => ring,
+    None => return 1.0,
+  };
+  let (center, radius) = body_sphere(uniforms);
+  let normal = ring.normal();
+
+  let facing = light_dir.dot(&normal);
+  if facing.abs() < 1e-4 {
+    return 1.0;
+  }
+  let t = (center - position).dot(&normal) / facing;
+  if t <= 0.0 {
+    return 1.0;
+  }
+
+  let hit = position + light_dir * t;
+  let (_, alpha) = ring.sample((hit - center).magnitude() / radius);
+  1.0 - alpha
+}
+
+// Anillos: color y opacidad del perfil radial, oscurecidos donde el planeta les hace sombra
+pub fn ring_shader(fragment: &Fragment, uniforms: &Uniforms) -> (Color, f32) {
+  let ring = match uniforms.ring {
+    Some(ring) => ring,
+    None => return (Color::black(), 0.0),
+  };
+
+  // Los vértices del anillo están en radios del planeta sobre el plano XZ
+  let p = fragment.vertex_position;
+  let (color, alpha) = ring.sample((p.x * p.x + p.z * p.z).sqrt());
+
+  let position = world_position(fragment, uniforms);
+  let light_dir = (uniforms.sun_position - position).normalize();
+  let (center, radius) = body_sphere(uniforms);
+
+  // ¿El rayo hacia el sol choca con el planeta?
+  let to_center = center - position;
+  let along = to_center.dot(&light_dir);
+  let closest = to_center.magnitude_squared() - along * along;
+  let in_shadow = along > 0.0 && closest < radius * radius;
+
+  // Las partículas del anillo reflejan luz por ambas caras
+  let light = if in_shadow {
+    0.15
+  } else {
+    0.4 + 0.6 * fragment.normal.dot(&light_dir).abs()
+  };
+
+  (shade(color, light), alpha)
+}
+
+// Altura de los continentes a partir de ondas superpuestas
+fn continent_height(x: f32, y: f32) -> f32 {
+  (
+      (x * 3.0).sin() * (y * 4.5).cos() * 0.7 +
+      (x * 1.5 + y * 2.0).sin() * 0.3 +
+      (x * 5.5).cos() * (y * 3.5).sin() * 0.2 +
+      ((x * 10.0).sin() * (y * 10.0).cos()).sin() * 0.1 +
+      ((x * 15.0 + y * 1.5).sin() * 0.5 + 0.5) * 0.15 +
+      ((x * 20.0).sin() * (y * 18.0).cos()).cos() * 0.05
+  ) * 0.5 + 0.5
+}
+
 
 pub fn earth_map_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Colores base para diferentes tonalidades de océano y tierra
@@ -74,14 +204,7 @@ pub fn earth_map_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let y = fragment.vertex_position.y;
 
   // Definición detallada de continentes usando ruido fractal
-  let land_factor = (
-      (x * 3.0).sin() * (y * 4.5).cos() * 0.7 +
-      (x * 1.5 + y * 2.0).sin() * 0.3 +
-      (x * 5.5).cos() * (y * 3.5).sin() * 0.2 +
-      ((x * 10.0).sin() * (y * 10.0).cos()).sin() * 0.1 +
-      ((x * 15.0 + y * 1.5).sin() * 0.5 + 0.5) * 0.15 +
-      ((x * 20.0).sin() * (y * 18.0).cos()).cos() * 0.05
-  ) * 0.5 + 0.5;
+  let land_factor = continent_height(x, y);
 
   // Selección de color para el océano y la tierra según el `land_factor`
   let base_color = if y.abs() > 0.8 {
@@ -103,7 +226,7 @@ pub fn earth_map_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Cálculo del ruido de las nubes
   let zoom = 50.0;  // Para mover nuestros valores
   let oy = 50.0;
-  let t = uniforms.time as f32 * 0.5;
+  let t = uniforms.days * 0.5;
 
   let noise_value = uniforms.noise.get_noise_2d(x * zoom  + t, y * zoom + oy);
 
@@ -121,8 +244,11 @@ pub fn earth_map_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Mezcla la base del color de la tierra con el color de las nubes
   let cloud_effect = noise_color.lerp(&base_color, 0.5); // Mezcla las nubes con el color de la tierra
 
+  // El relieve solo existe en tierra firme; el océano se mantiene plano
+  let intensity = surface_intensity(fragment, uniforms, |p| continent_height(p.x, p.y).max(0.5));
+
   // Ajusta el color final con la intensidad del fragmento
-  cloud_effect * fragment.intensity.max(0.2)
+  shade(cloud_effect, intensity.max(0.2))
 }
 
 pub fn water_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
@@ -133,14 +259,15 @@ pub fn water_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Coordenadas del fragmento y el tiempo para animación
   let x = fragment.vertex_position.x;
   let y = fragment.vertex_position.y;
-  let time = uniforms.time as f32 * 0.1; // Factor de tiempo para animación
+  let time = uniforms.days * 0.1; // Factor de tiempo para animación
 
   // Calcula las coordenadas rotadas en 90 grados en sentido horario
   let rotated_x = y;
   let rotated_y = -x;
 
   // Generación de ruido de ondas en capas para la superficie del agua
-  let wave_noise = uniforms.noise.get_noise_3d(rotated_x * 5.0 * 10.0, rotated_y * 5.0 * 10.0, time) * 0.5 + 0.5;
+  let waves = |px: f32, py: f32| uniforms.noise.get_noise_3d(px * 5.0 * 10.0, py * 5.0 * 10.0, time) * 0.5 + 0.5;
+  let wave_noise = waves(rotated_x, rotated_y);
 
   // Ajusta el color del agua de acuerdo al valor del ruido
   let water_color = deep_water_color.lerp(&shallow_water_color, wave_noise);
@@ -157,8 +284,11 @@ pub fn water_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Ajusta el color final mezclando el color base y la distorsión de la superficie
   let final_color = water_color.lerp(&deep_water_color, surface_noise * 0.5);
 
+  // Las crestas de las olas inclinan la normal
+  let intensity = surface_intensity(fragment, uniforms, |p| waves(p.y, -p.x));
+
   // Ajusta el color final con la intensidad del fragmento para dar brillo o sombra
-  final_color * fragment.intensity.max(0.2)
+  shade(final_color, intensity.max(0.2))
 }
 
 pub fn rock_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
@@ -173,11 +303,14 @@ pub fn rock_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
   // Generación de ruido para simular la superficie rocosa
   // Utilizamos diferentes escalas para amplificar la variabilidad del ruido
-  let base_noise = uniforms.noise.get_noise_3d(x * 5.0, y * 5.0, 0.0) * 0.5 + 0.5; // Ruido base
-  let noise_variation = uniforms.noise.get_noise_3d(x * 30.0, y * 30.0, 0.0) * 0.5 + 0.5; // Ruido adicional para variabilidad
+  let rock_height = |px: f32, py: f32| {
+    let base_noise = uniforms.noise.get_noise_3d(px * 5.0, py * 5.0, 0.0) * 0.5 + 0.5; // Ruido base
+    let noise_variation = uniforms.noise.get_noise_3d(px * 30.0, py * 30.0, 0.0) * 0.5 + 0.5; // Ruido adicional para variabilidad
 
-  // Ajustamos la intensidad del ruido base para crear un rango más amplio entre oscuro y claro
-  let adjusted_noise = base_noise * 0.7 + noise_variation * 0.3; // Mezcla de ruidos
+    // Ajustamos la intensidad del ruido base para crear un rango más amplio entre oscuro y claro
+    base_noise * 0.7 + noise_variation * 0.3 // Mezcla de ruidos
+  };
+  let adjusted_noise = rock_height(x, y);
 
   // Interpolación de colores en función del valor del ruido
   // Ajusta el color de la superficie con tonos de roca utilizando el ruido generado
@@ -186,8 +319,11 @@ pub fn rock_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Usamos el ruido para darle más profundidad, haciendo las áreas más oscuras más pronunciadas
   let final_color = base_rock_color.lerp(&black_color, adjusted_noise * 0.5);
 
+  // Los cráteres del ruido celular proyectan relieve
+  let intensity = surface_intensity(fragment, uniforms, |p| rock_height(p.x, p.y));
+
   // Ajusta el color final con la intensidad del fragmento para simular sombras
-  final_color * fragment.intensity.max(0.2)
+  shade(final_color, intensity.max(0.2))
 }
 
 pub fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
@@ -200,7 +336,7 @@ pub fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Coordenadas del fragmento y el tiempo
   let x = fragment.vertex_position.x;
   let y = fragment.vertex_position.y;
-  let time = uniforms.time as f32 * 0.1; // Tiempo para animar las manchas solares
+  let time = uniforms.days * 0.1; // Tiempo para animar las manchas solares
 
   // Generación de ruido para simular las variaciones en la superficie del sol
   let surface_noise = uniforms.noise.get_noise_3d(x * 50.0 , y * 50.0, time) * 0.5 + 0.5;
@@ -217,8 +353,8 @@ pub fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Mezclar el color final con el resplandor rojo
   let final_color_with_red = final_color + red_glow;
 
-  // Ajusta el color final con la intensidad del fragmento para simular el brillo
-  final_color_with_red
+  // El sol emite su propia luz; la emisión del material lo lleva por encima de 1.0 y florece
+  final_color_with_red * uniforms.material.emission
 }
 
 pub fn planet1_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
@@ -235,7 +371,8 @@ pub fn planet1_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
   // Generación de ruido para simular la textura de la superficie rocoso
   // El ruido ya no depende del tiempo, asegurando que la textura sea estática
-  let noise_value = uniforms.noise.get_noise_3d(x * 60.0, y * 60.0, 0.0) * 0.5 + 0.5;
+  let terrain = |px: f32, py: f32| uniforms.noise.get_noise_3d(px * 60.0, py * 60.0, 0.0) * 0.5 + 0.5;
+  let noise_value = terrain(x, y);
 
   // Mapeo de valores de ruido a diferentes colores de superficie para un planeta rocoso
   let planet_color = if noise_value < 0.2 {
@@ -254,8 +391,11 @@ pub fn planet1_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       violet_color
   };
 
+  // El mismo ruido sirve como mapa de alturas del terreno
+  let intensity = surface_intensity(fragment, uniforms, |p| terrain(p.x, p.y));
+
   // Ajusta el color final con la intensidad del fragmento para simular iluminación o sombra
-  planet_color * fragment.intensity.max(0.2)
+  shade(planet_color, intensity.max(0.2))
 }
 
 pub fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
@@ -268,10 +408,11 @@ pub fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Coordenadas del fragmento y el tiempo para animación
   let x = fragment.vertex_position.x;
   let y = fragment.vertex_position.y;
-  let time = uniforms.time as f32 * 0.1; // Tiempo para animar el gas
+  let time = uniforms.days * 0.1; // Tiempo para animar el gas
 
   // Generación de ruido para simular la dinámica del gas (nubes moviéndose)
-  let surface_noise = uniforms.noise.get_noise_3d(x * 15.0, y * 15.0, time) * 0.5 + 0.5;
+  let bands = |px: f32, py: f32| uniforms.noise.get_noise_3d(px * 15.0, py * 15.0, time) * 0.5 + 0.5;
+  let surface_noise = bands(x, y);
   
   // El color base del planeta gaseoso se ajusta según el ruido para simular nubes
   let cloud_color = cloud_base_color.lerp(&gas_color_1, surface_noise);
@@ -288,7 +429,9 @@ pub fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let final_gas_color = cloud_color.lerp(&gas_color_1, gas_noise_1);
   let final_color = final_gas_color.lerp(&gas_color_2, gas_noise_2);
 
-  final_color * fragment.intensity.max(0.2)
+  let intensity = surface_intensity(fragment, uniforms, |p| bands(p.x, p.y));
+
+  shade(final_color, intensity.max(0.2))
 }
 
 pub fn lava_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
@@ -301,10 +444,11 @@ pub fn lava_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Coordenadas del fragmento y el tiempo
   let x = fragment.vertex_position.x;
   let y = fragment.vertex_position.y;
-  let time = uniforms.time as f32 * 0.05; // Tiempo para animar la lava (más lento)
+  let time = uniforms.days * 0.05; // Tiempo para animar la lava (más lento)
 
   // Generación de ruido para simular la variación de la superficie
-  let surface_noise = uniforms.noise.get_noise_3d(x * 150.0, y * 150.0, time) * 0.5 + 0.5;
+  let surface = |px: f32, py: f32| uniforms.noise.get_noise_3d(px * 150.0, py * 150.0, time) * 0.5 + 0.5;
+  let surface_noise = surface(x, y);
 
   // Generar una variación de lava (el ruido se mueve para simular la lava)
   let lava_noise = uniforms.noise.get_noise_3d(x * 100.0, y * 100.0, time * 0.2) * 0.5 + 0.5;
@@ -323,10 +467,15 @@ pub fn lava_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Mezcla el color de la roca con el de la lava (áreas de lava activa y enfriada)
   let final_surface_color = rock_surface.lerp(&cooled_lava_surface, surface_noise * 0.5); // Aumentar la mezcla de lava
 
-  // Ajusta el color final con la intensidad del fragmento
-  let final_color = final_surface_color * fragment.intensity.max(0.2);
+  // Las coladas de lava enfriada forman crestas que reciben la luz
+  let intensity = surface_intensity(fragment, uniforms, |p| surface(p.x, p.y));
+
+  // La lava más caliente brilla por sí misma, también en el lado nocturno
+  let glow = ((lava_noise - 0.55) / 0.25).clamp(0.0, 1.0);
+  let emissive = lava_surface * glow * uniforms.material.emission;
 
-  final_color
+  // Ajusta el color final con la intensidad del fragmento
+  shade(final_surface_color, intensity.max(0.2)) + emissive
 }
 
 pub fn gray_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
@@ -341,11 +490,14 @@ pub fn gray_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
   // Generación de ruido para simular una textura sutil en la superficie de la nave
   // Usamos escalas de ruido para crear variabilidad suave
-  let base_noise = uniforms.noise.get_noise_3d(x * 2.0, y * 2.0, 0.0) * 0.5 + 0.5; // Ruido base
-  let noise_variation = uniforms.noise.get_noise_3d(x * 10.0, y * 10.0, 0.0) * 0.5 + 0.5; // Ruido adicional para variabilidad
+  let plating = |px: f32, py: f32| {
+    let base_noise = uniforms.noise.get_noise_3d(px * 2.0, py * 2.0, 0.0) * 0.5 + 0.5; // Ruido base
+    let noise_variation = uniforms.noise.get_noise_3d(px * 10.0, py * 10.0, 0.0) * 0.5 + 0.5; // Ruido adicional para variabilidad
 
-  // Ajustamos la intensidad del ruido base para suavizar las transiciones de luz y sombra
-  let adjusted_noise = base_noise * 0.8 + noise_variation * 0.2; // Mezcla de ruidos
+    // Ajustamos la intensidad del ruido base para suavizar las transiciones de luz y sombra
+    base_noise * 0.8 + noise_variation * 0.2 // Mezcla de ruidos
+  };
+  let adjusted_noise = plating(x, y);
 
   // Interpolación de colores en función del valor del ruido
   let base_gray_color = dark_gray.lerp(&light_gray, adjusted_noise);
@@ -353,8 +505,10 @@ pub fn gray_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Usamos el ruido para darle más profundidad, haciendo las áreas más oscuras más pronunciadas
   let final_color = base_gray_color.lerp(&black_color, adjusted_noise * 0.3);
 
+  let intensity = surface_intensity(fragment, uniforms, |p| plating(p.x, p.y));
+
   // Ajusta el color final con la intensidad del fragmento para simular sombras
-  final_color * fragment.intensity
+  shade(final_color, intensity)
 }
 
 
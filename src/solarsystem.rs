@@ -0,0 +1,550 @@
+use nalgebra_glm::{DMat3, Vec3};
+use crate::atmosphere::Atmosphere;
+use crate::color::Color;
+use crate::cuerpo::{Cuerpo, DistanceScale, Orbit};
+use crate::ephemeris::{self, Elements, OrbitalElements, AU_KM, DAYS_PER_CENTURY};
+use crate::material::Material;
+use crate::obj::Obj;
+use crate::ring::{Ring, RingProfile};
+
+const EARTH_RADIUS: f64 = 6371.0; // km
+// En escala real una unidad de la escena son 1000 km: la Tierra mide 6,4 unidades y Neptuno orbita a 4,5 millones
+const KM_PER_UNIT: f64 = 1000.0;
+// En escala legible los radios crecen con la raíz del real, para que los planetas chicos se vean
+const READABLE_EARTH_RADIUS: f64 = 0.12;
+
+// Escala del sistema solar real: las distancias y tamaños verdaderos, o una versión comprimida en la
+// que todo el sistema entra en pantalla y los planetas se distinguen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SystemScale {
+    True,
+    Readable,
+}
+
+impl SystemScale {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "true" => Some(SystemScale::True),
+            "readable" => Some(SystemScale::Readable),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SystemScale::True => "real",
+            SystemScale::Readable => "legible",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            SystemScale::True => SystemScale::Readable,
+            SystemScale::Readable => SystemScale::True,
+        }
+    }
+
+    // Cuánto más grande es el sistema que en escala legible, medido en la órbita de Neptuno. Sirve para
+    // llevar a esta escala las distancias de la cámara y las etiquetas, pensadas para el sistema legible.
+    pub fn distance_scale(&self) -> f64 {
+        let neptune = 30.07 * AU_KM;
+        self.planet_distances().distance(neptune) / SystemScale::Readable.planet_distances().distance(neptune)
+    }
+
+    fn planet_distances(&self) -> DistanceScale {
+        match self {
+            SystemScale::True => DistanceScale::Linear { km_per_unit: KM_PER_UNIT },
+            SystemScale::Readable => DistanceScale::Compressed { reference: AU_KM, base: 1.6, factor: 2.4 },
+        }
+    }
+
+    // Las lunas se alejan de su planeta en radios del planeta, así no quedan adentro de él
+    fn moon_distances(&self, parent_radius: f64, parent_scale: f64) -> DistanceScale {
+        match self {
+            SystemScale::True => DistanceScale::Linear { km_per_unit: KM_PER_UNIT },
+            SystemScale::Readable => DistanceScale::Compressed {
+                reference: parent_radius,
+                base: 1.5 * parent_scale,
+                factor: 0.35 * parent_scale,
+            },
+        }
+    }
+
+    fn radius(&self, km: f64) -> f64 {
+        match self {
+            SystemScale::True => km / KM_PER_UNIT,
+            SystemScale::Readable => READABLE_EARTH_RADIUS * (km / EARTH_RADIUS).sqrt(),
+        }
+    }
+}
+
+// Plano en que se miden los elementos de una órbita
+enum Plane {
+    Ecliptic,
+    Equator, // El ecuador del cuerpo central
+}
+
+struct BodyData {
+    name: &'static str,
+    parent: Option<&'static str>,
+    radius: f64, // Radio medio en km
+    orbit: Option<(OrbitalElements, Plane)>,
+    pole: (f64, f64),     // Ascensión recta y declinación del polo norte IAU en J2000, en grados
+    rotation_period: f64, // Sidéreo en días; negativo si gira al revés alrededor del polo norte IAU
+    shader: usize,
+    material: Material,
+    color: Color,
+}
+
+impl BodyData {
+    // Eje de giro según la regla de la mano derecha: el polo norte IAU, invertido si la rotación es retrógrada
+    fn spin_axis(&self) -> nalgebra_glm::DVec3 {
+        ephemeris::pole_direction(self.pole.0, self.pole.1) * self.rotation_period.signum()
+    }
+}
+
+// Elementos de E. M. Standish, "Keplerian Elements for Approximate Positions of the Major Planets"
+// (JPL), tabla 1, válida de 1800 a 2050: a en UA, e, i, L, longitud del perihelio y del nodo en
+// grados, y sus variaciones por siglo juliano. La de la Tierra es la del baricentro Tierra-Luna.
+fn planet(epoch: [f64; 6], rate: [f64; 6]) -> Option<(OrbitalElements, Plane)> {
+    let elements = |v: [f64; 6]| Elements {
+        semi_major_axis: v[0] * AU_KM,
+        eccentricity: v[1],
+        inclination: v[2],
+        mean_longitude: v[3],
+        longitude_of_periapsis: v[4],
+        longitude_of_node: v[5],
+    };
+    Some((OrbitalElements { epoch: elements(epoch), rate: elements(rate) }, Plane::Ecliptic))
+}
+
+// Luna en el plano ecuatorial de su planeta: semieje en km, período sidéreo en días. Sin datos de la
+// fase en J2000, la longitud media arranca en cero. Todas giran sincrónicas, con el eje de su planeta.
+fn moon(semi_major_axis: f64, eccentricity: f64, inclination: f64, period: f64) -> Option<(OrbitalElements, Plane)> {
+    let epoch = Elements { semi_major_axis, eccentricity, inclination, ..Elements::default() };
+    let rate = Elements { mean_longitude: 360.0 * DAYS_PER_CENTURY / period, ..Elements::default() };
+    Some((OrbitalElements { epoch, rate }, Plane::Equator))
+}
+
+fn bodies() -> Vec<BodyData> {
+    vec![
+        BodyData {
+            name: "Sol",
+            parent: None,
+            radius: 695_700.0,
+            orbit: None,
+            pole: (286.13, 63.87),
+            rotation_period: 25.38,
+            shader: 0,
+            material: Material::new(0.0, 4.0),
+            color: Color::new(255, 210, 120),
+        },
+        BodyData {
+            name: "Mercurio",
+            parent: Some("Sol"),
+            radius: 2439.7,
+            orbit: planet(
+                [0.38709927, 0.20563593, 7.00497902, 252.25032350, 77.45779628, 48.33076593],
+                [0.00000037, 0.00001906, -0.00594749, 149472.67411175, 0.16047689, -0.12534081],
+            ),
+            pole: (281.0103, 61.4155),
+            rotation_period: 58.6462,
+            shader: 6,
+            material: Material::new(1.0, 0.0),
+            color: Color::new(170, 160, 150),
+        },
+        BodyData {
+            name: "Venus",
+            parent: Some("Sol"),
+            radius: 6051.8,
+            orbit: planet(
+                [0.72333566, 0.00677672, 3.39467605, 181.97909950, 131.60246718, 76.67984255],
+                [0.00000390, -0.00004107, -0.00078890, 58517.81538729, 0.00268329, -0.27769418],
+            ),
+            pole: (272.76, 67.16),
+            rotation_period: -243.0226,
+            shader: 5,
+            material: Material::new(0.0, 0.0),
+            color: Color::new(230, 200, 140),
+        },
+        BodyData {
+            name: "Tierra",
+            parent: Some("Sol"),
+            radius: EARTH_RADIUS,
+            orbit: planet(
+                [1.00000261, 0.01671123, -0.00001531, 100.46457166, 102.93768193, 0.0],
+                [0.00000562, -0.00004392, -0.01294668, 35999.37244981, 0.32327364, 0.0],
+            ),
+            pole: (0.0, 90.0),
+            rotation_period: 0.99726968,
+            shader: 3,
+            material: Material::new(0.6, 0.0),
+            color: Color::new(90, 150, 230),
+        },
+        BodyData {
+            name: "Luna",
+            parent: Some("Tierra"),
+            radius: 1737.4,
+            // Elementos medios de Meeus (cap. 47) sobre la eclíptica; el nodo retrocede una vuelta cada
+            // 18,6 años y el perigeo avanza una cada 8,85
+            orbit: Some((
+                OrbitalElements {
+                    epoch: Elements {
+                        semi_major_axis: 384_400.0,
+                        eccentricity: 0.0549,
+                        inclination: 5.145,
+                        mean_longitude: 218.3164477,
+                        longitude_of_periapsis: 83.3532465,
+                        longitude_of_node: 125.0445479,
+                    },
+                    rate: Elements {
+                        mean_longitude: 481267.88123421,
+                        longitude_of_periapsis: 4069.0137287,
+                        longitude_of_node: -1934.1362891,
+                        ..Elements::default()
+                    },
+                },
+                Plane::Ecliptic,
+            )),
+            pole: (269.9949, 66.5392),
+            rotation_period: 27.321661,
+            shader: 7,
+            material: Material::new(0.8, 0.0),
+            color: Color::new(190, 190, 190),
+        },
+        BodyData {
+            name: "Marte",
+            parent: Some("Sol"),
+            radius: 3389.5,
+            orbit: planet(
+                [1.52371034, 0.09339410, 1.84969142, -4.55343205, -23.94362959, 49.55953891],
+                [0.00001847, 0.00007882, -0.00813131, 19140.30268499, 0.44441088, -0.29257343],
+            ),
+            pole: (317.68143, 52.88650),
+            rotation_period: 1.02595676,
+            shader: 6,
+            material: Material::new(0.6, 0.0),
+            color: Color::new(220, 110, 70),
+        },
+        BodyData {
+            name: "Júpiter",
+            parent: Some("Sol"),
+            radius: 69_911.0,
+            orbit: planet(
+                [5.20288700, 0.04838624, 1.30439695, 34.39644051, 14.72847983, 100.47390909],
+                [-0.00011607, -0.00013253, -0.00183714, 3034.74612775, 0.21252668, 0.20469106],
+            ),
+            pole: (268.056595, 64.495303),
+            rotation_period: 0.41354,
+            shader: 5,
+            material: Material::new(0.0, 0.0),
+            color: Color::new(215, 180, 140),
+        },
+        BodyData {
+            name: "Ío",
+            parent: Some("Júpiter"),
+            radius: 1821.6,
+            orbit: moon(421_800.0, 0.004, 0.04, 1.769138),
+            pole: (268.056595, 64.495303),
+            rotation_period: 1.769138,
+            shader: 1,
+            material: Material::new(0.3, 1.0),
+            color: Color::new(235, 220, 110),
+        },
+        BodyData {
+            name: "Europa",
+            parent: Some("Júpiter"),
+            radius: 1560.8,
+            orbit: moon(671_100.0, 0.009, 0.47, 3.551181),
+            pole: (268.056595, 64.495303),
+            rotation_period: 3.551181,
+            shader: 7,
+            material: Material::new(0.2, 0.0),
+            color: Color::new(210, 200, 180),
+        },
+        BodyData {
+            name: "Ganímedes",
+            parent: Some("Júpiter"),
+            radius: 2631.2,
+            orbit: moon(1_070_400.0, 0.0013, 0.18, 7.154553),
+            pole: (268.056595, 64.495303),
+            rotation_period: 7.154553,
+            shader: 6,
+            material: Material::new(0.6, 0.0),
+            color: Color::new(160, 150, 140),
+        },
+        BodyData {
+            name: "Calisto",
+            parent: Some("Júpiter"),
+            radius: 2410.3,
+            orbit: moon(1_882_700.0, 0.0074, 0.19, 16.689018),
+            pole: (268.056595, 64.495303),
+            rotation_period: 16.689018,
+            shader: 6,
+            material: Material::new(0.8, 0.0),
+            color: Color::new(120, 110, 100),
+        },
+        BodyData {
+            name: "Saturno",
+            parent: Some("Sol"),
+            radius: 58_232.0,
+            orbit: planet(
+                [9.53667594, 0.05386179, 2.48599187, 49.95424423, 92.59887831, 113.66242448],
+                [-0.00125060, -0.00050991, 0.00193609, 1222.49362201, -0.41897216, -0.28867794],
+            ),
+            pole: (40.589, 83.537),
+            rotation_period: 0.44401,
+            shader: 5,
+            material: Material::new(0.0, 0.0),
+            color: Color::new(225, 205, 150),
+        },
+        BodyData {
+            name: "Titán",
+            parent: Some("Saturno"),
+            radius: 2574.7,
+            orbit: moon(1_221_870.0, 0.0288, 0.33, 15.945421),
+            pole: (40.589, 83.537),
+            rotation_period: 15.945421,
+            shader: 5,
+            material: Material::new(0.0, 0.0),
+            color: Color::new(220, 160, 80),
+        },
+        BodyData {
+            name: "Urano",
+            parent: Some("Sol"),
+            radius: 25_362.0,
+            orbit: planet(
+                [19.18916464, 0.04725744, 0.77263783, 313.23810451, 170.95427630, 74.01692503],
+                [-0.00196176, -0.00004397, -0.00242939, 428.48202785, 0.40805281, 0.04240589],
+            ),
+            pole: (257.311, -15.175),
+            rotation_period: -0.71833,
+            shader: 4,
+            material: Material::new(0.0, 0.0),
+            color: Color::new(160, 220, 230),
+        },
+        BodyData {
+            name: "Titania",
+            parent: Some("Urano"),
+            radius: 788.4,
+            orbit: moon(436_300.0, 0.0011, 0.08, 8.705872),
+            pole: (257.311, -15.175),
+            rotation_period: -8.705872,
+            shader: 7,
+            material: Material::new(0.6, 0.0),
+            color: Color::new(180, 170, 170),
+        },
+        BodyData {
+            name: "Neptuno",
+            parent: Some("Sol"),
+            radius: 24_622.0,
+            orbit: planet(
+                [30.06992276, 0.00859048, 1.77004347, -55.12002969, 44.96476227, 131.78422574],
+                [0.00026291, 0.00005105, 0.00035372, 218.45945325, -0.32241464, -0.00508664],
+            ),
+            // Con el término de precesión del polo evaluado en J2000
+            pole: (299.33, 42.95),
+            rotation_period: 0.67125,
+            shader: 4,
+            material: Material::new(0.1, 0.0),
+            color: Color::new(80, 120, 230),
+        },
+        BodyData {
+            name: "Tritón",
+            parent: Some("Neptuno"),
+            radius: 1353.4,
+            // Retrógrada: la inclinación pasa de 90°
+            orbit: moon(354_759.0, 0.000016, 156.865, 5.876854),
+            pole: (299.33, 42.95),
+            rotation_period: -5.876854,
+            shader: 7,
+            material: Material::new(0.4, 0.0),
+            color: Color::new(210, 190, 190),
+        },
+        BodyData {
+            name: "Plutón",
+            parent: Some("Sol"),
+            radius: 1188.3,
+            orbit: planet(
+                [39.48211675, 0.24882730, 17.14001206, 238.92903833, 224.06891629, 110.30393684],
+                [-0.00031596, 0.00005170, 0.00004818, 145.20780515, -0.04062942, -0.01183482],
+            ),
+            pole: (132.993, -6.163),
+            rotation_period: 6.387230,
+            shader: 7,
+            material: Material::new(0.4, 0.0),
+            color: Color::new(200, 180, 160),
+        },
+        BodyData {
+            name: "Caronte",
+            parent: Some("Plutón"),
+            radius: 606.0,
+            orbit: moon(19_591.0, 0.0002, 0.08, 6.387230),
+            pole: (132.993, -6.163),
+            rotation_period: 6.387230,
+            shader: 7,
+            material: Material::new(0.4, 0.0),
+            color: Color::new(160, 160, 160),
+        },
+    ]
+}
+
+// El Sol, los ocho planetas, Plutón y las lunas principales, en el orden en que cada luna sigue a su
+// planeta. Las posiciones salen de los elementos de cada órbita para los días transcurridos desde J2000.
+pub fn create(scale: SystemScale, saturn_rings: Option<&str>) -> Vec<Cuerpo> {
+    let sphere = Obj::load("assets/models/cuerpo2.obj").expect("Failed to load sphere model").get_vertex_array();
+    let data = bodies();
+    let index_of = |name: &str| data.iter().position(|body| body.name == name).expect("cuerpo central desconocido");
+
+    data.iter()
+        .map(|body| {
+            let parent = body.parent.map(index_of);
+            let orbit = match &body.orbit {
+                Some((elements, plane)) => {
+                    let central = &data[parent.expect("una órbita necesita un cuerpo central")];
+                    let frame = match plane {
+                        Plane::Ecliptic => DMat3::identity(),
+                        Plane::Equator => ephemeris::equator_frame(&central.spin_axis()),
+                    };
+                    let distances = match central.parent {
+                        None => scale.planet_distances(),
+                        Some(_) => scale.moon_distances(central.radius, scale.radius(central.radius)),
+                    };
+                    Orbit::Kepler { elements: *elements, frame, scale: distances }
+                }
+                None => Orbit::Circular { radius: 0.0, phase: 0.0, speed: 0.0 },
+            };
+            let pole: Vec3 = ephemeris::to_scene(&ephemeris::pole_direction(body.pole.0, body.pole.1)).cast();
+
+            let mut cuerpo = Cuerpo {
+                name: body.name.to_string(),
+                scale: scale.radius(body.radius) as f32,
+                vertex_array: sphere.clone(),
+                orbit,
+                parent,
+                pole,
+                rotation_speed: (std::f64::consts::TAU / body.rotation_period) as f32,
+                shader: body.shader,
+                material: body.material,
+                atmosphere: None,
+                ring: None,
+                color: body.color,
+                // Sin margen: la cámara puede bajar hasta la superficie y el cuerpo solo se deja de dibujar adentro
+                disappearance_buffer: 0.0,
+            };
+            match body.name {
+                "Venus" => cuerpo.atmosphere = Some(Atmosphere::new(Vec3::new(0.95, 0.8, 0.45), 6.0, 0.08, 0.2, 0.7)),
+                "Tierra" => cuerpo.atmosphere = Some(Atmosphere::new(Vec3::new(0.18, 0.41, 1.0), 6.6, 0.15, 0.1, 0.76)),
+                "Titán" => cuerpo.atmosphere = Some(Atmosphere::new(Vec3::new(0.9, 0.6, 0.3), 5.0, 0.15, 0.2, 0.7)),
+                "Saturno" => {
+                    // Anillos C a A, de 74 658 a 136 775 km, en el plano del ecuador. Sin perfil, bandas procedurales.
+                    let profile = saturn_rings
+                        .and_then(|filename| {
+                            Ring::load_profile(filename)
+                                .map_err(|error| eprintln!("No se pudo cargar el perfil de los anillos: {}", error))
+                                .ok()
+                        })
+                        .unwrap_or(RingProfile::Procedural {
+                            inner_color: Color::new(150, 130, 100),
+                            outer_color: Color::new(215, 195, 160),
+                        });
+                    let mut ring = Ring::new(1.28, 2.35, 0.0, 0.85, profile);
+                    ring.equator = cuerpo.orientation();
+                    cuerpo.ring = Some(ring);
+                }
+                _ => {}
+            }
+            cuerpo
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::DVec3;
+    use super::*;
+    use crate::calendar::CalendarDate;
+
+    fn body(name: &str) -> BodyData {
+        bodies().into_iter().find(|body| body.name == name).expect("cuerpo desconocido")
+    }
+
+    fn elements(name: &str) -> OrbitalElements {
+        body(name).orbit.expect("sin órbita").0
+    }
+
+    // Días desde J2000 de una fecha en TDB
+    fn days(date: &str) -> f64 {
+        CalendarDate::parse(date).expect("fecha inválida").julian_day() - ephemeris::J2000
+    }
+
+    // Día y valor del mínimo de `f` entre `from` y `to`, muestreando cada centésimo de día
+    fn minimum(from: f64, to: f64, f: impl Fn(f64) -> f64) -> (f64, f64) {
+        let steps = ((to - from) / 0.01) as usize;
+        (0..=steps)
+            .map(|i| from + i as f64 * 0.01)
+            .map(|day| (day, f(day)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap()
+    }
+
+    fn assert_near(value: f64, expected: f64, tolerance: f64, what: &str) {
+        assert!((value - expected).abs() <= tolerance, "{what}: {value} en vez de {expected} ± {tolerance}");
+    }
+
+    #[test]
+    fn earth_perihelion_and_aphelion() {
+        let earth = elements("Tierra");
+        let distance = |day: f64| earth.position(day).magnitude() / AU_KM;
+
+        let (day, perihelion) = minimum(days("2000-01-01"), days("2000-01-08"), distance);
+        assert_near(perihelion, 0.98329, 1e-5, "perihelio");
+        assert_near(day, days("2000-01-04"), 0.5, "día del perihelio");
+
+        let (day, aphelion) = minimum(days("2000-07-01"), days("2000-07-08"), |day| -distance(day));
+        assert_near(-aphelion, 1.01671, 1e-5, "afelio");
+        assert_near(day, days("2000-07-04 12:00"), 0.5, "día del afelio");
+    }
+
+    #[test]
+    fn mars_closest_approach_of_2003() {
+        let (earth, mars) = (elements("Tierra"), elements("Marte"));
+        let (day, distance) = minimum(days("2003-08-01"), days("2003-09-30"), |day| {
+            (mars.position(day) - earth.position(day)).magnitude() / AU_KM
+        });
+        assert_near(distance, 0.3730, 5e-5, "distancia mínima");
+        assert_near(day, days("2003-08-27 12:00"), 0.5, "día del acercamiento");
+    }
+
+    #[test]
+    fn venus_transit_of_2004() {
+        let day = days("2004-06-08 08:20");
+        let (earth, venus) = (elements("Tierra").position(day), elements("Venus").position(day));
+        // Visto desde la Tierra, Venus cae dentro del disco del Sol (0.26° de radio en junio)
+        let separation = (venus - earth).angle(&-earth).to_degrees();
+        assert!(separation < 0.26, "Venus a {separation}° del centro del Sol");
+    }
+
+    #[test]
+    fn lunar_elongation_at_eclipses() {
+        let (earth, moon) = (elements("Tierra"), elements("Luna"));
+        let elongation = |date: &str| {
+            let day = days(date);
+            moon.position(day).angle(&-earth.position(day)).to_degrees()
+        };
+        assert_near(elongation("2000-01-06 18:14"), 1.8, 0.1, "luna nueva");
+        assert_near(elongation("2000-01-21 04:44"), 179.0, 0.1, "eclipse lunar");
+        assert_near(elongation("1999-08-11 11:03"), 1.3, 0.1, "eclipse solar");
+    }
+
+    #[test]
+    fn axial_tilts() {
+        for (name, tilt) in [("Tierra", 23.44), ("Venus", 177.36), ("Saturno", 26.73), ("Urano", 97.77), ("Neptuno", 28.32)] {
+            let body = body(name);
+            let orbit = body.orbit.as_ref().unwrap().0;
+            let normal: DVec3 = orbit.position(0.0).cross(&orbit.position(1.0)).normalize();
+            assert_near(body.spin_axis().angle(&normal).to_degrees(), tilt, 0.01, name);
+        }
+    }
+}
@@ -0,0 +1,397 @@
+use std::f32::consts::{PI, TAU};
+use std::fs;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use fastnoise_lite::{FastNoiseLite, NoiseType, FractalType};
+use rand::Rng;
+use crate::color::Color;
+use crate::ephemeris::OBLIQUITY;
+use crate::framebuffer::Framebuffer;
+
+// Polo norte y centro galácticos en coordenadas ecuatoriales (J2000, grados)
+const GALACTIC_POLE: (f32, f32) = (192.859_5, 27.128_25);
+const GALACTIC_CENTER: (f32, f32) = (266.405, -28.936);
+
+// Resolución de la textura equirectangular de la Vía Láctea (longitud x latitud galáctica)
+const MILKY_WAY_WIDTH: usize = 512;
+const MILKY_WAY_HEIGHT: usize = 256;
+
+// Separación en pixeles de la rejilla donde se evalúa la dirección del cielo
+const SKY_GRID: usize = 4;
+
+pub struct Star {
+    pub direction: Vec3, // Dirección unitaria en el marco de la escena
+    pub magnitude: f32,  // Magnitud aparente: menor = más brillante
+    pub color: Color,    // Color del cuerpo negro a su temperatura, con el canal mayor en 1.0
+}
+
+// Cielo de fondo: estrellas sobre la esfera celeste y, opcionalmente, la banda de la Vía Láctea.
+// Se dibuja por dirección, así que queda fijo respecto al universo cuando la cámara gira.
+pub struct Starfield {
+    pub stars: Vec<Star>,
+    pub brightness: f32, // Flujo de una estrella de magnitud 1
+    milky_way: Option<SkyTexture>,
+}
+
+// Textura HDR indexada por longitud y latitud galácticas
+struct SkyTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Starfield {
+    pub fn new(stars: Vec<Star>, brightness: f32) -> Self {
+        Starfield { stars, brightness, milky_way: None }
+    }
+
+    // Estrellas al azar distribuidas uniformemente en la esfera. Las magnitudes siguen la ley
+    // del cielo real (hay unas tres veces más estrellas por cada magnitud más débil)
+    pub fn procedural<R: Rng>(rng: &mut R, count: usize, limiting_magnitude: f32) -> Vec<Star> {
+        let brightest = 1.0f32;
+        let growth = 10f32.powf(0.45);
+        let span = growth.powf(limiting_magnitude - brightest) - 1.0;
+
+        (0..count)
+            .map(|_| {
+                let z: f32 = rng.gen_range(-1.0..1.0);
+                let angle: f32 = rng.gen_range(0.0..TAU);
+                let radius = (1.0 - z * z).sqrt();
+                let direction = Vec3::new(radius * angle.cos(), z, radius * angle.sin());
+
+                let magnitude = brightest + (1.0 + rng.gen::<f32>() * span).ln() / growth.ln();
+                // Predominan las estrellas tipo sol y más frías; pocas muy calientes
+                let temperature = 10f32.powf(rng.gen_range(3.45f32..4.0).min(rng.gen_range(3.5f32..4.4)));
+
+                Star { direction, magnitude, color: temperature_to_color(temperature) }
+            })
+            .collect()
+    }
+
+    // Catálogo CSV con encabezado. Columnas reconocidas:
+    //   ra    ascensión recta en grados (o `ra_h` en horas)
+    //   dec   declinación en grados
+    //   mag   magnitud aparente
+    //   bv    índice de color B-V (opcional; también se acepta `temp` en kelvin)
+    pub fn load_catalogue(filename: &str) -> Result<Vec<Star>, String> {
+        let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        Self::parse_catalogue(&contents, filename)
+    }
+
+    // `filename` solo se usa en los mensajes de error
+    fn parse_catalogue(contents: &str, filename: &str) -> Result<Vec<Star>, String> {
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+
+        let (_, header) = lines.next().ok_or_else(|| format!("{}: el catálogo está vacío", filename))?;
+        let columns: Vec<String> = header.split(',').map(|c| c.trim().to_lowercase()).collect();
+        let column = |name: &str| columns.iter().position(|c| c == name);
+
+        let (ra, ra_hours) = match (column("ra"), column("ra_h")) {
+            (Some(index), _) => (index, false),
+            (None, Some(index)) => (index, true),
+            _ => return Err(format!("{}: falta la columna `ra` o `ra_h`", filename)),
+        };
+        let dec = column("dec").ok_or_else(|| format!("{}: falta la columna `dec`", filename))?;
+        let mag = column("mag").ok_or_else(|| format!("{}: falta la columna `mag`", filename))?;
+        let (bv, temp) = (column("bv"), column("temp"));
+
+        let mut stars = Vec::new();
+        for (number, line) in lines {
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            let field = |index: usize| -> Result<f32, String> {
+                fields
+                    .get(index)
+                    .ok_or_else(|| format!("{}:{}: faltan columnas", filename, number + 1))?
+                    .parse::<f32>()
+                    .map_err(|e| format!("{}:{}: {}", filename, number + 1, e))
+            };
+
+            let right_ascension = if ra_hours { field(ra)? * 15.0 } else { field(ra)? };
+            let temperature = match (bv, temp) {
+                (Some(index), _) => bv_to_temperature(field(index)?),
+                (None, Some(index)) => field(index)?,
+                _ => 5800.0,
+            };
+            stars.push(Star {
+                direction: equatorial_direction(right_ascension, field(dec)?),
+                magnitude: field(mag)?,
+                color: temperature_to_color(temperature),
+            });
+        }
+        Ok(stars)
+    }
+
+    // Calcula una vez la banda galáctica: un disco difuso con el bulbo hacia el centro
+    // y franjas de polvo oscuro sobre el plano
+    pub fn with_milky_way(mut self, intensity: f32) -> Self {
+        let mut clouds = FastNoiseLite::with_seed(2024);
+        clouds.set_noise_type(Some(NoiseType::OpenSimplex2));
+        clouds.set_fractal_type(Some(FractalType::FBm));
+        clouds.set_fractal_octaves(Some(5));
+        clouds.set_frequency(Some(2.5));
+
+        let mut dust = FastNoiseLite::with_seed(77);
+        dust.set_noise_type(Some(NoiseType::OpenSimplex2));
+        dust.set_fractal_type(Some(FractalType::Ridged));
+        dust.set_fractal_octaves(Some(4));
+        dust.set_frequency(Some(4.0));
+
+        let core = Color::from_float(1.0, 0.82, 0.62);
+        let arms = Color::from_float(0.72, 0.8, 1.0);
+
+        let mut pixels = Vec::with_capacity(MILKY_WAY_WIDTH * MILKY_WAY_HEIGHT);
+        for y in 0..MILKY_WAY_HEIGHT {
+            let latitude = ((y as f32 + 0.5) / MILKY_WAY_HEIGHT as f32 - 0.5) * PI;
+            for x in 0..MILKY_WAY_WIDTH {
+                let longitude = (x as f32 + 0.5) / MILKY_WAY_WIDTH as f32 * TAU;
+                // Dirección en el marco galáctico, para muestrear el ruido sin costuras
+                let p = Vec3::new(
+                    latitude.cos() * longitude.cos(),
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                );
+                let l = if longitude > PI { longitude - TAU } else { longitude };
+
+                // Visto desde adentro, el disco parece más ancho hacia el anticentro, que está más cerca
+                let thickness = 0.1 + 0.06 * (l.abs() / PI);
+                let disk = (-(latitude / thickness).powi(2)).exp();
+                let bulge = (-(l / 0.45).powi(2) - (latitude / 0.2).powi(2)).exp();
+
+                let mottling = 0.6 + 0.4 * clouds.get_noise_3d(p.x, p.y, p.z);
+                let lanes = (-(latitude / 0.05).powi(2)).exp() * dust.get_noise_3d(p.x, p.y, p.z).clamp(0.0, 1.0);
+                let density = ((disk * 0.6 + bulge) * mottling * (1.0 - 0.85 * lanes)).max(0.0);
+
+                let color = arms.lerp(&core, bulge / (disk * 0.6 + bulge + 1e-4));
+                pixels.push(color * (density * intensity));
+            }
+        }
+
+        self.milky_way = Some(SkyTexture { width: MILKY_WAY_WIDTH, height: MILKY_WAY_HEIGHT, pixels });
+        self
+    }
+
+    // `view_matrix` solo aporta su rotación: el cielo está infinitamente lejos.
+    // `pixel_area` compensa el supersampling, donde cada pixel del buffer es una fracción del final.
+    pub fn draw(&self, framebuffer: &mut Framebuffer, view_matrix: &Mat4, projection_matrix: &Mat4, pixel_area: f32) {
+        let mut rotation = *view_matrix;
+        rotation.fixed_view_mut::<3, 1>(0, 3).fill(0.0);
+        let view_projection = projection_matrix * rotation;
+        let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+
+        if let Some(milky_way) = &self.milky_way {
+            self.draw_milky_way(framebuffer, milky_way, &view_projection);
+        }
+
+        for star in &self.stars {
+            let clip = view_projection * Vec4::new(star.direction.x, star.direction.y, star.direction.z, 0.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+            let x = (clip.x / clip.w + 1.0) * 0.5 * width;
+            let y = (1.0 - clip.y / clip.w) * 0.5 * height;
+            let flux = self.brightness * 10f32.powf(-0.4 * (star.magnitude - 1.0)) * pixel_area;
+            // Se reparte entre los cuatro pixeles vecinos para que no titile al moverse
+            framebuffer.splat(x - 0.5, y - 0.5, star.color * flux);
+        }
+    }
+
+    fn draw_milky_way(&self, framebuffer: &mut Framebuffer, texture: &SkyTexture, view_projection: &Mat4) {
+        let inverse = match view_projection.try_inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+        let (width, height) = (framebuffer.width, framebuffer.height);
+
+        // La dirección (sin normalizar) es afín en pantalla: basta con la esquina y dos incrementos.
+        // Se desproyecta en z = 0 y no en el plano lejano, que con `far` enorme queda en el infinito.
+        let ray = |x: f32, y: f32| {
+            let ndc = Vec4::new(x / width as f32 * 2.0 - 1.0, 1.0 - y / height as f32 * 2.0, 0.0, 1.0);
+            let point = inverse * ndc;
+            point.xyz() / point.w
+        };
+        let origin = ray(0.5, 0.5);
+        let step_x = ray(1.5, 0.5) - origin;
+        let step_y = ray(0.5, 1.5) - origin;
+
+        let (pole, center) = galactic_basis();
+        let east = pole.cross(&center);
+
+        // Un texel abarca varios pixeles, así que la textura se muestrea en una rejilla gruesa
+        // y cada pixel interpola entre los cuatro puntos que lo rodean
+        let columns = width.div_ceil(SKY_GRID) + 1;
+        let rows = height.div_ceil(SKY_GRID) + 1;
+        let mut grid = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let (x, y) = ((column * SKY_GRID) as f32, (row * SKY_GRID) as f32);
+                let direction = (origin + step_x * x + step_y * y).normalize();
+                let longitude = direction.dot(&east).atan2(direction.dot(&center)).rem_euclid(TAU);
+                let latitude = direction.dot(&pole).clamp(-1.0, 1.0).asin();
+                grid.push(texture.sample(longitude / TAU, latitude / PI + 0.5));
+            }
+        }
+
+        let cell = 1.0 / SKY_GRID as f32;
+        for y in 0..height {
+            let (row, ty) = (y / SKY_GRID, (y % SKY_GRID) as f32 * cell);
+            for x in 0..width {
+                let (column, tx) = (x / SKY_GRID, (x % SKY_GRID) as f32 * cell);
+                let at = |column: usize, row: usize| grid[row * columns + column];
+                let top = at(column, row).lerp(&at(column + 1, row), tx);
+                let bottom = at(column, row + 1).lerp(&at(column + 1, row + 1), tx);
+                framebuffer.add_light(x, y, top.lerp(&bottom, ty));
+            }
+        }
+    }
+}
+
+impl SkyTexture {
+    // Bilineal, con la coordenada u periódica
+    fn sample(&self, u: f32, v: f32) -> Color {
+        let x = u * self.width as f32 - 0.5;
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let x0 = x.floor();
+        let (tx, y0) = (x - x0, y.floor() as usize);
+        let ty = y - y0 as f32;
+        let x0 = (x0 as i64).rem_euclid(self.width as i64) as usize;
+        let x1 = (x0 + 1) % self.width;
+        let y1 = (y0 + 1).min(self.height - 1);
+
+        let at = |x: usize, y: usize| self.pixels[y * self.width + x];
+        let top = at(x0, y0).lerp(&at(x1, y0), tx);
+        let bottom = at(x0, y1).lerp(&at(x1, y1), tx);
+        top.lerp(&bottom, ty)
+    }
+}
+
+// La escena usa el plano eclíptico como plano XZ con +Y hacia el norte de la eclíptica;
+// +X apunta al punto vernal y Z = -Y eclíptico para conservar la mano derecha
+pub fn ecliptic_to_scene(v: Vec3) -> Vec3 {
+    Vec3::new(v.x, v.z, -v.y)
+}
+
+// Ascensión recta y declinación (grados) a una dirección de la escena
+pub fn equatorial_direction(right_ascension: f32, declination: f32) -> Vec3 {
+    let (ra, dec) = (right_ascension.to_radians(), declination.to_radians());
+    let equatorial = Vec3::new(dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin());
+    // Rotación alrededor del eje X (punto vernal) del ecuador a la eclíptica
+    let (sin, cos) = (OBLIQUITY as f32).to_radians().sin_cos();
+    let ecliptic = Vec3::new(
+        equatorial.x,
+        equatorial.y * cos + equatorial.z * sin,
+        -equatorial.y * sin + equatorial.z * cos,
+    );
+    ecliptic_to_scene(ecliptic)
+}
+
+// Polo norte galáctico y dirección al centro galáctico, ortonormalizados, en el marco de la escena
+fn galactic_basis() -> (Vec3, Vec3) {
+    let pole = equatorial_direction(GALACTIC_POLE.0, GALACTIC_POLE.1);
+    let center = equatorial_direction(GALACTIC_CENTER.0, GALACTIC_CENTER.1);
+    let center = (center - pole * center.dot(&pole)).normalize();
+    (pole, center)
+}
+
+// Fórmula de Ballesteros (2012) para la temperatura efectiva a partir del índice B-V
+pub fn bv_to_temperature(bv: f32) -> f32 {
+    4600.0 * (1.0 / (0.92 * bv + 1.7) + 1.0 / (0.92 * bv + 0.62))
+}
+
+// Aproximación del color de un cuerpo negro (ajuste de Tanner Helland, 1000 K - 40000 K),
+// normalizada para que el canal más intenso valga 1.0
+pub fn temperature_to_color(kelvin: f32) -> Color {
+    let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+    let red = if t <= 66.0 { 255.0 } else { 329.698_73 * (t - 60.0).powf(-0.133_204_76) };
+    let green = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+
+    let channel = |value: f32| value.clamp(0.0, 255.0) as u8;
+    let color = Color::new(channel(red), channel(green), channel(blue));
+    let peak = color.r.max(color.g).max(color.b).max(1e-4);
+    color * (1.0 / peak)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<Vec<Star>, String> {
+        Starfield::parse_catalogue(contents, "prueba.csv")
+    }
+
+    fn error(contents: &str) -> String {
+        parse(contents).err().expect("el catálogo no debería aceptarse")
+    }
+
+    fn assert_same_direction(a: Vec3, b: Vec3) {
+        assert!((a - b).magnitude() < 1e-5, "{a:?} en vez de {b:?}");
+    }
+
+    fn assert_same_color(a: Color, b: Color) {
+        assert_eq!(a.to_hex(), b.to_hex(), "{a:?} en vez de {b:?}");
+    }
+
+    #[test]
+    fn header_follows_comments_and_names_the_columns() {
+        let stars = parse("# Estrellas de prueba\n\n  MAG , Dec, RA\n-1.46, -16.716, 101.287\n\n# Vega\n0.03, 38.784, 279.234\n").unwrap();
+        assert_eq!(stars.len(), 2);
+        assert_eq!(stars[0].magnitude, -1.46);
+        assert_same_direction(stars[0].direction, equatorial_direction(101.287, -16.716));
+        assert_same_direction(stars[1].direction, equatorial_direction(279.234, 38.784));
+        // Sin columna de color se usa una temperatura solar
+        assert_same_color(stars[1].color, temperature_to_color(5800.0));
+    }
+
+    #[test]
+    fn right_ascension_in_hours() {
+        let stars = parse("ra_h,dec,mag\n6,0,1\n18.5,45,2\n").unwrap();
+        assert_same_direction(stars[0].direction, equatorial_direction(90.0, 0.0));
+        assert_same_direction(stars[1].direction, equatorial_direction(277.5, 45.0));
+    }
+
+    #[test]
+    fn color_from_bv_or_temperature() {
+        let stars = parse("ra,dec,mag,bv\n0,0,1,0.65\n0,0,1,-0.3\n").unwrap();
+        assert_same_color(stars[0].color, temperature_to_color(bv_to_temperature(0.65)));
+        assert_same_color(stars[1].color, temperature_to_color(bv_to_temperature(-0.3)));
+        // Las estrellas azules tienen el azul como canal más intenso, las tipo sol el rojo
+        assert_eq!(stars[1].color.b, 1.0);
+        assert_eq!(stars[0].color.r, 1.0);
+
+        let stars = parse("ra,dec,mag,temp\n0,0,1,3500\n").unwrap();
+        assert_same_color(stars[0].color, temperature_to_color(3500.0));
+        // Si están las dos columnas manda B-V
+        let stars = parse("ra,dec,mag,temp,bv\n0,0,1,3500,0.0\n").unwrap();
+        assert_same_color(stars[0].color, temperature_to_color(bv_to_temperature(0.0)));
+    }
+
+    #[test]
+    fn rejects_malformed_catalogues() {
+        assert!(error("# nada\n\n").contains("vacío"));
+        assert!(error("dec,mag\n0,1\n").contains("`ra`"));
+        assert!(error("ra,mag\n0,1\n").contains("`dec`"));
+        assert!(error("ra,dec\n0,1\n").contains("`mag`"));
+        assert!(error("ra,dec,mag\n0,0,1\n0,0\n").starts_with("prueba.csv:3:"));
+        assert!(error("ra,dec,mag\n\n0,x,1\n").starts_with("prueba.csv:3:"));
+    }
+
+    #[test]
+    fn celestial_pole_matches_the_obliquity() {
+        // El polo norte celeste queda inclinado la oblicuidad respecto al polo de la eclíptica (+Y)
+        let pole = equatorial_direction(0.0, 90.0);
+        let tilt = pole.y.clamp(-1.0, 1.0).acos().to_degrees();
+        assert!((tilt - OBLIQUITY as f32).abs() < 1e-3, "{tilt}");
+    }
+}
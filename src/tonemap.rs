@@ -0,0 +1,114 @@
+use crate::color::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapOperator {
+    Clamp,    // Recorta en 1.0: el aspecto original, sin compresión de altas luces
+    Reinhard, // c / (1 + c) sobre la luminancia, conserva el tono
+    Aces,     // Aproximación de Narkowicz a la curva fílmica ACES
+}
+
+impl ToneMapOperator {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToneMapOperator::Clamp => "clamp",
+            ToneMapOperator::Reinhard => "reinhard",
+            ToneMapOperator::Aces => "aces",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ToneMapOperator::Clamp => ToneMapOperator::Reinhard,
+            ToneMapOperator::Reinhard => ToneMapOperator::Aces,
+            ToneMapOperator::Aces => ToneMapOperator::Clamp,
+        }
+    }
+}
+
+pub struct ToneMapping {
+    pub operator: ToneMapOperator,
+    pub exposure: f32, // En paradas (EV): cada unidad duplica la luz
+}
+
+impl ToneMapping {
+    pub fn new(operator: ToneMapOperator, exposure: f32) -> Self {
+        ToneMapping { operator, exposure }
+    }
+
+    // Lleva un color HDR lineal al rango [0, 1], todavía en espacio lineal
+    pub fn apply(&self, color: Color) -> Color {
+        let color = color * 2.0f32.powf(self.exposure);
+
+        match self.operator {
+            ToneMapOperator::Clamp => Color::from_float(color.r.min(1.0), color.g.min(1.0), color.b.min(1.0)),
+            ToneMapOperator::Reinhard => {
+                let luminance = color.luminance();
+                if luminance <= 0.0 {
+                    return Color::black();
+                }
+                let mapped = color * (1.0 / (1.0 + luminance));
+                Color::from_float(mapped.r.min(1.0), mapped.g.min(1.0), mapped.b.min(1.0))
+            }
+            ToneMapOperator::Aces => Color::from_float(aces(color.r), aces(color.g), aces(color.b)),
+        }
+    }
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping::new(ToneMapOperator::Clamp, 0.0)
+    }
+}
+
+fn aces(x: f32) -> f32 {
+    let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+    ((x * (a * x + b)) / (x * (c * x + d) + e)).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(value: f32, expected: f32, what: &str) {
+        assert!((value - expected).abs() < 1e-5, "{what}: {value} en vez de {expected}");
+    }
+
+    #[test]
+    fn reinhard_compresses_luminance_and_keeps_hue() {
+        let reinhard = ToneMapping::new(ToneMapOperator::Reinhard, 0.0);
+        let white = reinhard.apply(Color::from_float(1.0, 1.0, 1.0));
+        assert_near(white.r, 0.5, "blanco");
+
+        // c / (1 + L) escala los tres canales por igual
+        let color = Color::from_float(0.8, 0.4, 0.2);
+        let mapped = reinhard.apply(color);
+        let scale = 1.0 / (1.0 + color.luminance());
+        assert_near(mapped.r, 0.8 * scale, "rojo");
+        assert_near(mapped.g / mapped.r, 0.5, "tono");
+        assert_near(mapped.b / mapped.r, 0.25, "tono");
+
+        let black = reinhard.apply(Color::black());
+        assert_eq!((black.r, black.g, black.b), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn aces_curve() {
+        let aces = ToneMapping::new(ToneMapOperator::Aces, 0.0);
+        let map = |value: f32| aces.apply(Color::from_float(value, value, value)).r;
+        assert_near(map(0.0), 0.0, "negro");
+        // (2.51 + 0.03) / (2.43 + 0.59 + 0.14)
+        assert_near(map(1.0), 2.54 / 3.16, "uno");
+        assert_near(map(100.0), 1.0, "saturado");
+        let samples: Vec<f32> = (0..=64).map(|i| map(i as f32 / 8.0)).collect();
+        assert!(samples.windows(2).all(|pair| pair[0] <= pair[1]), "la curva tiene que ser creciente");
+    }
+
+    #[test]
+    fn exposure_is_in_stops() {
+        let clamp = ToneMapping::new(ToneMapOperator::Clamp, 1.0);
+        let mapped = clamp.apply(Color::from_float(0.25, 0.5, 2.0));
+        assert_near(mapped.r, 0.5, "una parada duplica la luz");
+        assert_near(mapped.g, 1.0, "una parada duplica la luz");
+        assert_near(mapped.b, 1.0, "clamp recorta en 1");
+    }
+}
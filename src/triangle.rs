@@ -1,79 +1,450 @@
-use nalgebra_glm::{Vec3, dot, Vec2};
+use std::time::Duration;
+use nalgebra_glm::{Vec3, Vec2};
 use crate::fragment::Fragment;
-use crate::vertex::{self, Vertex};
-use crate::color::Color;
+use crate::vertex::Vertex;
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
-  let mut fragments = Vec::new();
+// Precisión sub-pixel: las posiciones en pantalla se redondean a 1/256 de pixel
+const SUBPIXEL_BITS: u32 = 8;
+const SUBPIXEL_ONE: i64 = 1 << SUBPIXEL_BITS;
+
+// La máscara de cobertura es un u8: hasta 8 muestras por pixel
+pub const MAX_SAMPLES: usize = 8;
+
+// Las filas se evalúan por tramos de este ancho para no reservar memoria por triángulo
+const SPAN: usize = 64;
+
+// Más allá de este rango (en pixeles) los productos de las funciones de borde podrían desbordar
+const MAX_COORDINATE: f32 = (1 << 20) as f32;
+
+// Contadores del rasterizador para medir su rendimiento
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RasterStats {
+  pub triangles: usize,
+  pub fragments: usize,
+  pub time: Duration,
+}
+
+impl RasterStats {
+  pub fn add(&mut self, other: &RasterStats) {
+    self.triangles += other.triangles;
+    self.fragments += other.fragments;
+    self.time += other.time;
+  }
+}
+
+// Posición en pantalla en punto fijo
+#[derive(Clone, Copy, Default)]
+struct FixedPoint {
+  x: i64,
+  y: i64,
+}
+
+impl FixedPoint {
+  fn from_screen(x: f32, y: f32) -> Self {
+    FixedPoint {
+      x: (x * SUBPIXEL_ONE as f32).round() as i64,
+      y: (y * SUBPIXEL_ONE as f32).round() as i64,
+    }
+  }
+}
+
+// Función de borde entera, normalizada para ser positiva dentro del triángulo.
+// `bias` aplica la regla top-left: un punto exactamente sobre el borde solo cuenta si el borde
+// es superior o izquierdo, así dos triángulos vecinos nunca cubren dos veces el mismo punto.
+struct Edge {
+  a: FixedPoint,
+  b: FixedPoint,
+  sign: i64,
+  bias: i64,
+  step_x: i64, // Incremento al avanzar un pixel en x
+  step_y: i64, // Incremento al avanzar un pixel en y
+}
+
+impl Edge {
+  fn new(a: FixedPoint, b: FixedPoint, sign: i64) -> Self {
+    // Gradiente de la función (ya orientada): dE/dx y dE/dy
+    let gradient_x = (b.y - a.y) * sign;
+    let gradient_y = -(b.x - a.x) * sign;
+    let top_left = gradient_x > 0 || (gradient_x == 0 && gradient_y > 0);
+    Edge {
+      a,
+      b,
+      sign,
+      bias: if top_left { 0 } else { -1 },
+      step_x: gradient_x * SUBPIXEL_ONE,
+      step_y: gradient_y * SUBPIXEL_ONE,
+    }
+  }
+
+  fn evaluate(&self, p: FixedPoint) -> i64 {
+    edge_function(self.a, self.b, p) * self.sign
+  }
+
+  fn contains(&self, value: i64) -> bool {
+    value + self.bias >= 0
+  }
+}
+
+// `samples` son las posiciones de muestreo dentro del pixel; con una sola muestra en (0.5, 0.5)
+// se obtiene el rasterizado clásico por centro de pixel. Solo se recorren los pixeles
+// de la caja envolvente que caen dentro de la pantalla de `width` x `height`.
+// Los fragmentos se agregan a `fragments`.
+pub fn triangle(
+  v1: &Vertex,
+  v2: &Vertex,
+  v3: &Vertex,
+  samples: &[Vec2],
+  width: usize,
+  height: usize,
+  fragments: &mut Vec<Fragment>,
+) {
+  assert!(samples.len() <= MAX_SAMPLES, "a lo sumo {} muestras por pixel", MAX_SAMPLES);
   let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
 
-  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+  // Triángulos degenerados o detrás de la cámara (coordenadas no finitas o enormes)
+  let valid = |v: &Vec3| v.x.abs() < MAX_COORDINATE && v.y.abs() < MAX_COORDINATE;
+  if !(valid(&a) && valid(&b) && valid(&c)) {
+    return;
+  }
+
+  let (fa, fb, fc) = (
+    FixedPoint::from_screen(a.x, a.y),
+    FixedPoint::from_screen(b.x, b.y),
+    FixedPoint::from_screen(c.x, c.y),
+  );
+
+  let triangle_area = edge_function(fa, fb, fc);
+  if triangle_area == 0 {
+    return;
+  }
+
+  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(fa, fb, fc);
+  let (min_x, min_y) = (min_x.max(0), min_y.max(0));
+  let (max_x, max_y) = (max_x.min(width as i64 - 1), max_y.min(height as i64 - 1));
+  if min_x > max_x || min_y > max_y {
+    return;
+  }
+
+  // Cada peso se mide con el borde opuesto a su vértice; el signo del área uniformiza el sentido de giro
+  let sign = triangle_area.signum();
+  let edges = [Edge::new(fb, fc, sign), Edge::new(fc, fa, sign), Edge::new(fa, fb, sign)];
+  let area = (triangle_area * sign) as f64;
 
-  let light_dir = Vec3::new(0.0, 0.0, 1.0);
+  let barycentric = |p: FixedPoint| {
+    let w1 = (edges[0].evaluate(p) as f64 / area) as f32;
+    let w2 = (edges[1].evaluate(p) as f64 / area) as f32;
+    let w3 = (edges[2].evaluate(p) as f64 / area) as f32;
+    (w1, w2, w3)
+  };
+
+  // La profundidad es lineal en pantalla: con sus derivadas se obtiene en cualquier muestra
+  let depth_at = |p: FixedPoint| {
+    let (w1, w2, w3) = barycentric(p);
+    a.z * w1 + b.z * w2 + c.z * w3
+  };
+  let origin = FixedPoint { x: min_x * SUBPIXEL_ONE, y: min_y * SUBPIXEL_ONE };
+  let origin_depth = depth_at(origin);
+  let depth_slope = Vec2::new(
+    depth_at(FixedPoint { x: origin.x + SUBPIXEL_ONE, y: origin.y }) - origin_depth,
+    depth_at(FixedPoint { x: origin.x, y: origin.y + SUBPIXEL_ONE }) - origin_depth,
+  );
 
-  let triangle_area = edge_function(&a, &b, &c);
+  let mut sample_offsets = [FixedPoint::default(); MAX_SAMPLES];
+  for (offset, sample) in sample_offsets.iter_mut().zip(samples.iter()) {
+    *offset = FixedPoint::from_screen(sample.x, sample.y);
+  }
+  let center_offset = FixedPoint { x: SUBPIXEL_ONE / 2, y: SUBPIXEL_ONE / 2 };
+
+  // Las funciones de borde se evalúan una sola vez en la esquina de la caja para cada muestra;
+  // desde ahí se avanzan sumando su incremento por fila y por columna
+  let mut row_start = [[0i64; 3]; MAX_SAMPLES];
+  for (values, offset) in row_start.iter_mut().zip(sample_offsets.iter()).take(samples.len()) {
+    let point = FixedPoint { x: origin.x + offset.x, y: origin.y + offset.y };
+    *values = [edges[0].evaluate(point), edges[1].evaluate(point), edges[2].evaluate(point)];
+  }
+  let row_start = &mut row_start[..samples.len()];
+  let mut span_start = [[0i64; 3]; MAX_SAMPLES];
+  let mut span_coverage = [0u8; SPAN];
 
-  // Iterate over each pixel in the bounding box
   for y in min_y..=max_y {
-    for x in min_x..=max_x {
-      let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+    for span_x in (min_x..=max_x).step_by(SPAN) {
+      // Máscara de cobertura del tramo: qué muestras de cada pixel caen dentro del triángulo
+      let span_width = ((max_x - span_x + 1) as usize).min(SPAN);
+      let columns = span_x - min_x;
+      for (start, values) in span_start.iter_mut().zip(row_start.iter()) {
+        for i in 0..3 {
+          start[i] = values[i] + edges[i].step_x * columns;
+        }
+      }
+      coverage_row(&edges, &span_start[..samples.len()], &mut span_coverage[..span_width]);
 
-      // Calculate barycentric coordinates
-      let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
+      for (column, &coverage) in span_coverage[..span_width].iter().enumerate() {
+        if coverage == 0 {
+          continue;
+        }
+        let x = span_x + column as i64;
+        let pixel = FixedPoint { x: x * SUBPIXEL_ONE, y: y * SUBPIXEL_ONE };
 
-      // Check if the point is inside the triangle
-      if w1 >= 0.0 && w1 <= 1.0 && 
-         w2 >= 0.0 && w2 <= 1.0 &&
-         w3 >= 0.0 && w3 <= 1.0 {
+        // Se sombrea una vez por pixel: en el centro si está cubierto, si no en la primera
+        // muestra cubierta para no extrapolar atributos fuera del triángulo
+        let center = FixedPoint { x: pixel.x + center_offset.x, y: pixel.y + center_offset.y };
+        let full = coverage.count_ones() as usize == samples.len();
+        let shading_point = if full || samples.len() == 1 {
+          center
+        } else {
+          let offset = sample_offsets[coverage.trailing_zeros() as usize];
+          FixedPoint { x: pixel.x + offset.x, y: pixel.y + offset.y }
+        };
+        let (w1, w2, w3) = barycentric(shading_point);
 
         // Interpolate normal
         let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
         let normal = normal.normalize();
 
-        // Calculate lighting intensity
-        let intensity = dot(&normal, &light_dir).max(0.0);
-
-        // Create a gray color (unchanged)
-        let color = Color::new(100, 100, 100); // Medium gray
-
-        // Interpolate depth
-        let depth = a.z * w1 + b.z * w2 + c.z * w3;
+        // Depth at the pixel centre; each sample adds its offset along the slope
+        let depth = depth_at(center);
 
         // Positions of the original vertex
         let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
 
-        fragments.push(Fragment::new(
+        let mut fragment = Fragment::new(
             Vec2::new(x as f32, y as f32),
-            color,
             depth,
             normal,
-            intensity,
             vertex_position,
-        ));
+        );
+        fragment.tex_coords = v1.tex_coords * w1 + v2.tex_coords * w2 + v3.tex_coords * w3;
+        fragment.coverage = coverage;
+        fragment.depth_slope = depth_slope;
+        fragments.push(fragment);
       }
     }
+
+    for values in row_start.iter_mut() {
+      for (value, edge) in values.iter_mut().zip(edges.iter()) {
+        *value += edge.step_y;
+      }
+    }
+  }
+}
+
+// Cobertura de una fila: para cada muestra se recorre la fila sumando el incremento en x.
+// Con la característica `simd` y AVX2 disponible se evalúan 4 pixeles a la vez.
+fn coverage_row(edges: &[Edge; 3], row_start: &[[i64; 3]], coverage: &mut [u8]) {
+  #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+  {
+    if is_x86_feature_detected!("avx2") {
+      // SAFETY: la CPU soporta AVX2, recién comprobado
+      unsafe { simd::coverage_row_avx2(edges, row_start, coverage) };
+      return;
+    }
   }
 
-  fragments
+  coverage_row_scalar(edges, row_start, coverage);
+}
+
+fn coverage_row_scalar(edges: &[Edge; 3], row_start: &[[i64; 3]], coverage: &mut [u8]) {
+  coverage.fill(0);
+  for (sample, start) in row_start.iter().enumerate() {
+    let mut values = *start;
+    for mask in coverage.iter_mut() {
+      if edges.iter().zip(values.iter()).all(|(edge, value)| edge.contains(*value)) {
+        *mask |= 1 << sample;
+      }
+      for (value, edge) in values.iter_mut().zip(edges.iter()) {
+        *value += edge.step_x;
+      }
+    }
+  }
 }
 
-fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
-    let min_x = v1.x.min(v2.x).min(v3.x).floor() as i32;
-    let min_y = v1.y.min(v2.y).min(v3.y).floor() as i32;
-    let max_x = v1.x.max(v2.x).max(v3.x).ceil() as i32;
-    let max_y = v1.y.max(v2.y).max(v3.y).ceil() as i32;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd {
+  use std::arch::x86_64::*;
+  use super::Edge;
 
-    (min_x, min_y, max_x, max_y)
+  // Cuatro pixeles consecutivos por registro de 256 bits (enteros de 64 bits)
+  #[target_feature(enable = "avx2")]
+  pub(super) unsafe fn coverage_row_avx2(edges: &[Edge; 3], row_start: &[[i64; 3]], coverage: &mut [u8]) {
+    coverage.fill(0);
+    let blocks = coverage.len() / 4;
+
+    for (sample, start) in row_start.iter().enumerate() {
+      let bit = 1u8 << sample;
+      let mut values = [_mm256_setzero_si256(); 3];
+      let mut steps = [_mm256_setzero_si256(); 3];
+      let mut thresholds = [_mm256_setzero_si256(); 3];
+      for i in 0..3 {
+        let (value, step) = (start[i], edges[i].step_x);
+        values[i] = _mm256_set_epi64x(value + 3 * step, value + 2 * step, value + step, value);
+        steps[i] = _mm256_set1_epi64x(4 * step);
+        // value + bias >= 0  <=>  value > -bias - 1
+        thresholds[i] = _mm256_set1_epi64x(-edges[i].bias - 1);
+      }
+
+      for block in 0..blocks {
+        let inside = _mm256_and_si256(
+          _mm256_and_si256(
+            _mm256_cmpgt_epi64(values[0], thresholds[0]),
+            _mm256_cmpgt_epi64(values[1], thresholds[1]),
+          ),
+          _mm256_cmpgt_epi64(values[2], thresholds[2]),
+        );
+        let lanes = _mm256_movemask_pd(_mm256_castsi256_pd(inside));
+        if lanes != 0 {
+          for lane in 0..4 {
+            if lanes & (1 << lane) != 0 {
+              coverage[block * 4 + lane] |= bit;
+            }
+          }
+        }
+        for i in 0..3 {
+          values[i] = _mm256_add_epi64(values[i], steps[i]);
+        }
+      }
+
+      // Los pixeles que no completan un bloque se evalúan uno por uno
+      let mut tail = [0i64; 3];
+      for (i, value) in tail.iter_mut().enumerate() {
+        *value = start[i] + edges[i].step_x * (blocks * 4) as i64;
+      }
+      for mask in coverage[blocks * 4..].iter_mut() {
+        if edges.iter().zip(tail.iter()).all(|(edge, value)| edge.contains(*value)) {
+          *mask |= bit;
+        }
+        for (value, edge) in tail.iter_mut().zip(edges.iter()) {
+          *value += edge.step_x;
+        }
+      }
+    }
+  }
 }
 
-fn barycentric_coordinates(p: &Vec3, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> (f32, f32, f32) {
-    let w1 = edge_function(b, c, p) / area;
-    let w2 = edge_function(c, a, p) / area;
-    let w3 = edge_function(a, b, p) / area;
+// Pixeles que pueden contener alguna muestra del triángulo
+fn calculate_bounding_box(v1: FixedPoint, v2: FixedPoint, v3: FixedPoint) -> (i64, i64, i64, i64) {
+    let min_x = v1.x.min(v2.x).min(v3.x).div_euclid(SUBPIXEL_ONE);
+    let min_y = v1.y.min(v2.y).min(v3.y).div_euclid(SUBPIXEL_ONE);
+    let max_x = v1.x.max(v2.x).max(v3.x).div_euclid(SUBPIXEL_ONE);
+    let max_y = v1.y.max(v2.y).max(v3.y).div_euclid(SUBPIXEL_ONE);
 
-    (w1, w2, w3)
+    (min_x, min_y, max_x, max_y)
 }
 
-fn edge_function(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
+fn edge_function(a: FixedPoint, b: FixedPoint, c: FixedPoint) -> i64 {
     (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::antialiasing::sample_pattern;
+
+  const WIDTH: usize = 64;
+  const HEIGHT: usize = 48;
+
+  // Centro del pixel más cercano, para que los bordes pasen exactamente por muestras
+  fn pixel_center(x: f32, y: f32) -> Vec3 {
+    Vec3::new(x.floor() + 0.5, y.floor() + 0.5, 0.5)
+  }
+
+  // Abanico de 37 triángulos cuyo contorno encierra toda la pantalla
+  fn fan() -> Vec<[Vec3; 3]> {
+    let center = pixel_center(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+    let rim: Vec<Vec3> = (0..37)
+      .map(|i| {
+        let angle = i as f32 / 37.0 * std::f32::consts::TAU;
+        pixel_center(center.x + 60.0 * angle.cos(), center.y + 60.0 * angle.sin())
+      })
+      .collect();
+    (0..rim.len()).map(|i| [center, rim[i], rim[(i + 1) % rim.len()]]).collect()
+  }
+
+  // Cuadrados de 5 pixeles partidos por una diagonal que alterna, con los dos sentidos de giro
+  fn split_quads() -> Vec<[Vec3; 3]> {
+    let mut triangles = Vec::new();
+    for row in -1..=(HEIGHT as i32 / 5) {
+      for column in -1..=(WIDTH as i32 / 5) {
+        let corner = |dx: i32, dy: i32| pixel_center(((column + dx) * 5) as f32, ((row + dy) * 5) as f32);
+        let (a, b, c, d) = (corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1));
+        if (row + column) % 2 == 0 {
+          triangles.push([a, b, c]);
+          triangles.push([a, d, c]);
+        } else {
+          triangles.push([a, b, d]);
+          triangles.push([b, c, d]);
+        }
+      }
+    }
+    triangles
+  }
+
+  // Veces que se cubrió cada muestra de la pantalla
+  fn coverage_counts(triangles: &[[Vec3; 3]], samples: &[Vec2]) -> Vec<u32> {
+    let mut counts = vec![0; WIDTH * HEIGHT * samples.len()];
+    let mut fragments = Vec::new();
+    for [a, b, c] in triangles {
+      let vertex = |position: &Vec3| Vertex::new(*position, Vec3::z(), Vec2::zeros());
+      triangle(&vertex(a), &vertex(b), &vertex(c), samples, WIDTH, HEIGHT, &mut fragments);
+    }
+    for fragment in &fragments {
+      let pixel = fragment.position.y as usize * WIDTH + fragment.position.x as usize;
+      for (sample, count) in counts[pixel * samples.len()..][..samples.len()].iter_mut().enumerate() {
+        *count += (fragment.coverage >> sample) as u32 & 1;
+      }
+    }
+    counts
+  }
+
+  // Con la característica `simd` y AVX2, `triangle` calcula la cobertura con la ruta vectorial
+  #[test]
+  fn every_sample_is_covered_exactly_once() {
+    for (name, triangles) in [("abanico", fan()), ("cuadrados", split_quads())] {
+      for samples in [1, 4] {
+        let counts = coverage_counts(&triangles, &sample_pattern(samples));
+        for (index, count) in counts.iter().enumerate() {
+          let pixel = index / samples;
+          assert_eq!(
+            *count, 1,
+            "{name} con {samples} muestras: la muestra {} del pixel ({}, {})",
+            index % samples, pixel % WIDTH, pixel / WIDTH,
+          );
+        }
+      }
+    }
+  }
+
+  #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+  #[test]
+  fn simd_coverage_matches_scalar() {
+    if !is_x86_feature_detected!("avx2") {
+      return;
+    }
+    for [a, b, c] in fan().iter().chain(split_quads().iter()) {
+      let (fa, fb, fc) = (FixedPoint::from_screen(a.x, a.y), FixedPoint::from_screen(b.x, b.y), FixedPoint::from_screen(c.x, c.y));
+      let sign = edge_function(fa, fb, fc).signum();
+      if sign == 0 {
+        continue;
+      }
+      let edges = [Edge::new(fb, fc, sign), Edge::new(fc, fa, sign), Edge::new(fa, fb, sign)];
+      for samples in [1, 4] {
+        for y in 0..HEIGHT as i64 {
+          let row_start: Vec<[i64; 3]> = sample_pattern(samples)
+            .iter()
+            .map(|offset| {
+              let point = FixedPoint::from_screen(offset.x, y as f32 + offset.y);
+              [edges[0].evaluate(point), edges[1].evaluate(point), edges[2].evaluate(point)]
+            })
+            .collect();
+          // Anchos que no son múltiplo de 4 para pasar también por la cola escalar
+          for width in [WIDTH, 61, 3] {
+            let (mut vector, mut scalar) = (vec![0u8; width], vec![0u8; width]);
+            // SAFETY: la CPU soporta AVX2, recién comprobado
+            unsafe { simd::coverage_row_avx2(&edges, &row_start, &mut vector) };
+            coverage_row_scalar(&edges, &row_start, &mut scalar);
+            assert_eq!(vector, scalar, "fila {y} con {samples} muestras");
+          }
+        }
+      }
+    }
+  }
+}
@@ -23,20 +23,13 @@ impl Vertex {
     }
   }
 
-  pub fn new_with_color(position: Vec3, color: Color) -> Self {
-    Vertex {
-      position,
-      normal: Vec3::new(0.0, 0.0, 0.0),
-      tex_coords: Vec2::new(0.0, 0.0),
-      color,
-      transformed_position: Vec3::new(0.0, 0.0, 0.0),
-      transformed_normal: Vec3::new(0.0, 0.0, 0.0),
-    }
-  }
-
-  pub fn set_transformed(&mut self, position: Vec3, normal: Vec3) {
-    self.transformed_position = position;
-    self.transformed_normal = normal;
+  // Punto intermedio de la arista en el espacio del modelo, para recortar contra el plano cercano
+  pub fn lerp(&self, other: &Vertex, t: f32) -> Self {
+    Vertex::new(
+      self.position.lerp(&other.position, t),
+      self.normal.lerp(&other.normal, t),
+      self.tex_coords.lerp(&other.tex_coords, t),
+    )
   }
 }
 
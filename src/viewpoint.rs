@@ -0,0 +1,227 @@
+use std::time::Instant;
+use nalgebra::UnitQuaternion;
+use nalgebra_glm::{DVec3, Vec3};
+use crate::camera::{Camera, DEFAULT_FOV};
+use crate::settings::Settings;
+
+// Cantidad de vistas con nombre, una por cada tecla 1-9
+pub const NAMED_SLOTS: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewpoint {
+    pub eye: DVec3, // En el mundo, en f64 como la cámara
+    pub center: DVec3,
+    pub up: Vec3,
+    pub fov: f32, // Vertical, en radianes
+}
+
+impl Viewpoint {
+    pub fn from_camera(camera: &Camera) -> Self {
+        Viewpoint { eye: camera.eye, center: camera.center, up: camera.up, fov: camera.fov }
+    }
+
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.eye = self.eye;
+        camera.center = self.center;
+        camera.up = self.up;
+        camera.fov = self.fov;
+    }
+
+    // Orientación de la vista: lleva el eje z a la dirección de la mirada y el eje y hacia `up`
+    fn orientation(&self) -> UnitQuaternion<f32> {
+        let forward = (self.center - self.eye).cast::<f32>().try_normalize(1e-6).unwrap_or(-Vec3::z());
+        // Con `up` paralelo a la mirada no hay orientación definida; cualquier perpendicular sirve
+        let up = if forward.cross(&self.up).magnitude() > 1e-4 {
+            self.up
+        } else if forward.x.abs() < 0.9 {
+            Vec3::x()
+        } else {
+            Vec3::y()
+        };
+        UnitQuaternion::face_towards(&forward, &up)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    Smooth, // Smoothstep
+    Cubic,  // Cúbica de entrada y salida, arranca y frena más suave
+    Sine,
+}
+
+impl Easing {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "linear" => Some(Easing::Linear),
+            "smooth" => Some(Easing::Smooth),
+            "cubic" => Some(Easing::Cubic),
+            "sine" => Some(Easing::Sine),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::Smooth => t * t * (3.0 - 2.0 * t),
+            Easing::Cubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::Sine => 0.5 - 0.5 * (std::f32::consts::PI * t).cos(),
+        }
+    }
+}
+
+// Animación de la cámara entre dos vistas. El centro se interpola en línea recta, la orientación con
+// slerp y la distancia al centro en escala logarítmica, para que acercarse desde muy lejos no sea brusco
+// al final. Si las dos vistas miran al mismo punto, la cámara lo rodea en lugar de atravesarlo.
+pub struct Transition {
+    from: Viewpoint,
+    to: Viewpoint,
+    from_orientation: UnitQuaternion<f32>,
+    to_orientation: UnitQuaternion<f32>,
+    easing: Easing,
+    duration: f32, // Segundos
+    start: Instant,
+}
+
+impl Transition {
+    pub fn new(from: Viewpoint, to: Viewpoint, duration: f32, easing: Easing) -> Self {
+        Transition {
+            from,
+            to,
+            from_orientation: from.orientation(),
+            to_orientation: to.orientation(),
+            easing,
+            duration,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn target(&self) -> Viewpoint {
+        self.to
+    }
+
+    // Cambia el destino sin reiniciar el recorrido, para perseguir algo que se mueve
+    pub fn retarget(&mut self, to: Viewpoint) {
+        self.to = to;
+        self.to_orientation = to.orientation();
+    }
+
+    // Vista en la fracción `t` del recorrido, ya con la curva de aceleración aplicada
+    pub fn at(&self, t: f32) -> Viewpoint {
+        let t = self.easing.apply(t);
+        if t >= 1.0 {
+            return self.to;
+        }
+
+        let center = self.from.center.lerp(&self.to.center, t as f64);
+        let from_distance = (self.from.eye - self.from.center).magnitude().max(1e-6);
+        let to_distance = (self.to.eye - self.to.center).magnitude().max(1e-6);
+        let distance = (from_distance.ln() + (to_distance.ln() - from_distance.ln()) * t as f64).exp();
+
+        // `slerp` toma el camino corto; en orientaciones opuestas elige un eje cualquiera
+        let orientation = self
+            .from_orientation
+            .try_slerp(&self.to_orientation, t, 1e-6)
+            .unwrap_or(if t < 0.5 { self.from_orientation } else { self.to_orientation });
+        let forward = orientation * Vec3::z();
+        Viewpoint {
+            eye: center - forward.cast() * distance,
+            center,
+            up: orientation * Vec3::y(),
+            fov: self.from.fov + (self.to.fov - self.from.fov) * t,
+        }
+    }
+
+    // Mueve la cámara según el tiempo transcurrido; devuelve `true` cuando llegó al destino
+    pub fn update(&self, camera: &mut Camera) -> bool {
+        let t = if self.duration > 0.0 { self.start.elapsed().as_secs_f32() / self.duration } else { 1.0 };
+        self.at(t).apply(camera);
+        t >= 1.0
+    }
+}
+
+// Vistas guardadas: una pila para volver a la anterior y hasta nueve vistas con nombre
+pub struct Viewpoints {
+    pub home: Viewpoint, // La vista con la que arranca el programa
+    stack: Vec<Viewpoint>,
+    named: Vec<Option<(String, Viewpoint)>>,
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl Viewpoints {
+    // Las vistas con nombre se leen de la sección `[views]`: `1.name`, `1.eye`, `1.center` y, opcionales,
+    // `1.up` y `1.fov` en grados
+    pub fn from_settings(settings: &Settings, home: Viewpoint) -> Self {
+        let named = (1..=NAMED_SLOTS)
+            .map(|slot| {
+                let eye = settings.get_dvec3(&format!("views.{}.eye", slot))?;
+                let center = settings.get_dvec3(&format!("views.{}.center", slot))?;
+                let up = settings.get_vec3(&format!("views.{}.up", slot)).unwrap_or(Vec3::y());
+                let fov = settings.get_f32(&format!("views.{}.fov", slot), DEFAULT_FOV.to_degrees()).to_radians();
+                let name = settings.get(&format!("views.{}.name", slot)).map_or(format!("vista {}", slot), str::to_string);
+                Some((name, Viewpoint { eye, center, up, fov }))
+            })
+            .collect();
+
+        let easing = match settings.get("camera.easing") {
+            Some(name) => Easing::parse(name).unwrap_or_else(|| {
+                eprintln!("Curva de transición desconocida: {}", name);
+                Easing::Cubic
+            }),
+            None => Easing::Cubic,
+        };
+
+        Viewpoints {
+            home,
+            stack: Vec::new(),
+            named,
+            duration: settings.get_f32("camera.transition", 1.0).max(0.0),
+            easing,
+        }
+    }
+
+    pub fn push(&mut self, viewpoint: Viewpoint) {
+        self.stack.push(viewpoint);
+    }
+
+    pub fn pop(&mut self) -> Option<Viewpoint> {
+        self.stack.pop()
+    }
+
+    // Guarda la vista en la posición `slot` (desde 0); si ya tenía nombre lo conserva
+    pub fn save(&mut self, slot: usize, viewpoint: Viewpoint) -> &str {
+        let name = match self.named[slot].take() {
+            Some((name, _)) => name,
+            None => format!("vista {}", slot + 1),
+        };
+        &self.named[slot].insert((name, viewpoint)).0
+    }
+
+    pub fn named(&self, slot: usize) -> Option<(&str, Viewpoint)> {
+        self.named.get(slot)?.as_ref().map(|(name, viewpoint)| (name.as_str(), *viewpoint))
+    }
+
+    // Multiplica por `factor` las posiciones de todas las vistas, cuando el sistema cambia de escala
+    pub fn rescale(&mut self, factor: f64) {
+        let scale = |viewpoint: &mut Viewpoint| {
+            viewpoint.eye *= factor;
+            viewpoint.center *= factor;
+        };
+        scale(&mut self.home);
+        self.stack.iter_mut().for_each(scale);
+        self.named.iter_mut().flatten().for_each(|(_, viewpoint)| scale(viewpoint));
+    }
+
+    pub fn transition(&self, from: Viewpoint, to: Viewpoint) -> Transition {
+        Transition::new(from, to, self.duration, self.easing)
+    }
+}